@@ -0,0 +1,335 @@
+use std::{fs, io, path::PathBuf};
+
+use crate::{highscore, stats::Stats};
+
+/// Loaded from `achievements.txt` in the repo root. See there for the format.
+const DEFINITIONS: &str = include_str!("../achievements.txt");
+
+const HEADER: &str = "roundhead-achievements";
+const VERSION: u32 = 1;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Comparison {
+	AtLeast,
+	AtMost,
+	Equal,
+	Above,
+	Below,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Clause {
+	stat: String,
+	comparison: Comparison,
+	value: f32,
+}
+
+impl Clause {
+	fn parse(clause: &str) -> Option<Self> {
+		// Two character operators first so >= isn't read as >
+		let operators = [
+			(">=", Comparison::AtLeast),
+			("<=", Comparison::AtMost),
+			("==", Comparison::Equal),
+			(">", Comparison::Above),
+			("<", Comparison::Below),
+		];
+
+		operators.iter().find_map(|(op, comparison)| {
+			let (stat, value) = clause.split_once(op)?;
+
+			Some(Self {
+				stat: stat.trim().to_owned(),
+				comparison: *comparison,
+				value: value.trim().parse().ok()?,
+			})
+		})
+	}
+
+	fn holds(&self, values: &Values) -> bool {
+		let current = match values.get(&self.stat) {
+			Some(current) => current,
+			None => return false,
+		};
+
+		match self.comparison {
+			Comparison::AtLeast => current >= self.value,
+			Comparison::AtMost => current <= self.value,
+			Comparison::Equal => current == self.value,
+			Comparison::Above => current > self.value,
+			Comparison::Below => current < self.value,
+		}
+	}
+}
+
+/// What conditions are checked against
+pub struct Values<'a> {
+	pub stats: &'a Stats,
+	pub score: f32,
+}
+
+impl<'a> Values<'a> {
+	fn get(&self, name: &str) -> Option<f32> {
+		match name {
+			"score" => Some(self.score),
+			_ => self.stats.value(name),
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Achievement {
+	pub id: String,
+	pub title: String,
+	pub description: String,
+	condition: Vec<Clause>,
+}
+
+impl Achievement {
+	fn parse(line: &str) -> Option<Self> {
+		let mut parts = line.split('|').map(str::trim);
+		let id = parts.next()?.to_owned();
+		let title = parts.next()?.to_owned();
+		let description = parts.next()?.to_owned();
+
+		let condition = parts
+			.next()?
+			.split("&&")
+			.map(Clause::parse)
+			.collect::<Option<Vec<Clause>>>()?;
+
+		Some(Self {
+			id,
+			title,
+			description,
+			condition,
+		})
+	}
+
+	fn met(&self, values: &Values) -> bool {
+		self.condition.iter().all(|clause| clause.holds(values))
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Achievements {
+	all: Vec<Achievement>,
+	/// The ids of the ones we've gotten, in any run
+	unlocked: Vec<String>,
+}
+
+/// Every achievement, none of them unlocked
+impl Default for Achievements {
+	fn default() -> Self {
+		Self {
+			all: Self::definitions(),
+			unlocked: vec![],
+		}
+	}
+}
+
+impl Achievements {
+	/// Parse the definitions and load what's already been unlocked
+	pub fn load() -> Self {
+		let header = format!("{HEADER} {VERSION}");
+		let unlocked = match Self::path().map(fs::read_to_string) {
+			Some(Ok(contents)) if contents.lines().next() == Some(header.as_str()) => {
+				contents.lines().skip(1).map(str::to_owned).collect()
+			}
+			_ => vec![],
+		};
+
+		Self {
+			unlocked,
+			..Self::default()
+		}
+	}
+
+	fn definitions() -> Vec<Achievement> {
+		DEFINITIONS
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.filter_map(|line| {
+				let parsed = Achievement::parse(line);
+				if parsed.is_none() {
+					eprintln!("Couldn't understand achievement: {line}");
+				}
+				parsed
+			})
+			.collect()
+	}
+
+	fn path() -> Option<PathBuf> {
+		highscore::data_dir().map(|dir| dir.join("roundhead").join("achievements"))
+	}
+
+	pub fn save(&self) -> io::Result<()> {
+		let path = Self::path()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+
+		let mut contents = format!("{HEADER} {VERSION}\n");
+		for id in &self.unlocked {
+			contents.push_str(id);
+			contents.push('\n');
+		}
+
+		highscore::write_atomically(&path, &contents)
+	}
+
+	pub fn is_unlocked(&self, achievement: &Achievement) -> bool {
+		self.unlocked.contains(&achievement.id)
+	}
+
+	pub fn all(&self) -> &[Achievement] {
+		&self.all
+	}
+
+	/// Unlock everything whose condition is now met. Returns the newly
+	/// unlocked ones.
+	pub fn check(&mut self, values: &Values) -> Vec<Achievement> {
+		let newly: Vec<Achievement> = self
+			.all
+			.iter()
+			.filter(|achievement| !self.is_unlocked(achievement) && achievement.met(values))
+			.cloned()
+			.collect();
+
+		if !newly.is_empty() {
+			self.unlocked
+				.extend(newly.iter().map(|achievement| achievement.id.clone()));
+
+			if let Err(e) = self.save() {
+				eprintln!("Couldn't save achievements: {e}");
+			}
+		}
+
+		newly
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn only(line: &str) -> Achievements {
+		Achievements {
+			all: vec![Achievement::parse(line).unwrap()],
+			unlocked: vec![],
+		}
+	}
+
+	fn with_kills(kills: u32) -> Stats {
+		let mut stats = Stats::default();
+		stats.burn_kills = kills;
+		stats
+	}
+
+	#[test]
+	fn two_character_operators_win() {
+		let clause = Clause::parse("kills >= 3").unwrap();
+		assert_eq!(clause.stat, "kills");
+		assert_eq!(clause.comparison, Comparison::AtLeast);
+		assert_eq!(clause.value, 3.0);
+
+		assert_eq!(
+			Clause::parse("kills<=3").unwrap().comparison,
+			Comparison::AtMost
+		);
+		assert_eq!(
+			Clause::parse("kills > 3").unwrap().comparison,
+			Comparison::Above
+		);
+	}
+
+	#[test]
+	fn at_least_and_above_differ() {
+		let stats = with_kills(3);
+		let values = Values {
+			stats: &stats,
+			score: 0.0,
+		};
+
+		assert!(Clause::parse("kills >= 3").unwrap().holds(&values));
+		assert!(!Clause::parse("kills > 3").unwrap().holds(&values));
+		assert!(Clause::parse("kills == 3").unwrap().holds(&values));
+		assert!(Clause::parse("kills < 4").unwrap().holds(&values));
+	}
+
+	#[test]
+	fn bad_lines_are_refused() {
+		assert_eq!(Clause::parse("kills 3"), None);
+		assert_eq!(Clause::parse("kills >= three"), None);
+
+		// No condition, and one bad clause in a chain
+		assert_eq!(Achievement::parse("id | Title | Description"), None);
+		assert_eq!(
+			Achievement::parse("id | Title | Description | kills >= 1 && waves"),
+			None
+		);
+	}
+
+	#[test]
+	fn every_clause_has_to_hold() {
+		let achievement =
+			Achievement::parse("id | Title | Description | kills >= 1 && walls_placed == 0")
+				.unwrap();
+		assert_eq!(achievement.condition.len(), 2);
+
+		let mut stats = with_kills(1);
+		let met = |stats: &Stats| achievement.met(&Values { stats, score: 0.0 });
+		assert!(met(&stats));
+
+		stats.walls_placed = 1;
+		assert!(!met(&stats));
+	}
+
+	#[test]
+	fn unknown_stats_never_hold() {
+		let mut achievements = only("id | Title | Description | nonsense >= 0");
+		let stats = with_kills(100);
+
+		let values = Values {
+			stats: &stats,
+			score: 100.0,
+		};
+		assert!(achievements.check(&values).is_empty());
+	}
+
+	#[test]
+	fn unlocks_once() {
+		let mut achievements = only("id | Title | Description | score > 10");
+		let stats = Stats::default();
+		let values = |score| Values {
+			stats: &stats,
+			score,
+		};
+
+		assert!(achievements.check(&values(5.0)).is_empty());
+		assert_eq!(achievements.check(&values(20.0)).len(), 1);
+		assert!(achievements.check(&values(30.0)).is_empty());
+		assert!(achievements.is_unlocked(&achievements.all()[0]));
+	}
+
+	#[test]
+	fn shipped_achievements_parse() {
+		let lines = DEFINITIONS
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.count();
+
+		let definitions = Achievements::definitions();
+		assert_eq!(definitions.len(), lines);
+
+		// A misspelled stat would parse fine and just never unlock
+		let stats = Stats::default();
+		let values = Values {
+			stats: &stats,
+			score: 0.0,
+		};
+		for clause in definitions.iter().flat_map(|a| &a.condition) {
+			assert!(values.get(&clause.stat).is_some(), "{}", clause.stat);
+		}
+	}
+}
@@ -0,0 +1,237 @@
+use std::time::Duration;
+
+use smitten::{Color, Vec2};
+
+use crate::util::Cooldown;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Looping {
+	/// Back to the first frame after the last
+	Loop,
+	/// Done after the last frame, and draws nothing after
+	Once,
+	/// Stay on the last frame
+	Hold,
+}
+
+/// Frames of an animation, each a sprite in the atlas
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clip {
+	pub frames: Vec<String>,
+	pub frame_time: Duration,
+	pub looping: Looping,
+}
+
+impl Clip {
+	/// Parse what comes after `clip <name>` in `atlas.txt`:
+	/// `<milliseconds per frame> <loop|once|hold> <frame> <frame> ...`
+	pub fn parse(words: &[&str]) -> Option<Self> {
+		let frame_time = Duration::from_millis(words.first()?.parse().ok()?);
+		let looping = match *words.get(1)? {
+			"loop" => Looping::Loop,
+			"once" => Looping::Once,
+			"hold" => Looping::Hold,
+			_ => return None,
+		};
+
+		let frames: Vec<String> = words[2..].iter().map(|s| s.to_string()).collect();
+		if frames.is_empty() || frame_time.is_zero() {
+			return None;
+		}
+
+		Some(Self {
+			frames,
+			frame_time,
+			looping,
+		})
+	}
+
+	pub fn length(&self) -> Duration {
+		self.frame_time * self.frames.len() as u32
+	}
+
+	pub fn is_finished(&self, elapsed: Duration) -> bool {
+		self.looping != Looping::Loop && elapsed >= self.length()
+	}
+
+	/// The sprite to show `elapsed` into the clip. None once a clip that plays
+	/// once is over.
+	pub fn frame(&self, elapsed: Duration) -> Option<&str> {
+		// In whole nanoseconds so a frame starts exactly on its boundary
+		let index = (elapsed.as_nanos() / self.frame_time.as_nanos()) as usize;
+		let last = self.frames.len() - 1;
+
+		let index = match self.looping {
+			Looping::Loop => index % self.frames.len(),
+			Looping::Once if index > last => return None,
+			Looping::Once => index,
+			Looping::Hold => index.min(last),
+		};
+
+		Some(&self.frames[index])
+	}
+}
+
+/// Which clip something is playing and how far into it it is
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Animator {
+	clip: &'static str,
+	elapsed: Duration,
+}
+
+impl Animator {
+	pub fn new(clip: &'static str) -> Self {
+		Self {
+			clip,
+			elapsed: Duration::ZERO,
+		}
+	}
+
+	pub fn clip(&self) -> &'static str {
+		self.clip
+	}
+
+	pub fn elapsed(&self) -> Duration {
+		self.elapsed
+	}
+
+	/// Switch to `clip` from the start, unless it's already playing
+	pub fn play(&mut self, clip: &'static str) {
+		if self.clip != clip {
+			self.clip = clip;
+			self.elapsed = Duration::ZERO;
+		}
+	}
+
+	pub fn tick(&mut self, delta: Duration) {
+		self.elapsed += delta;
+	}
+}
+
+/// An animation that plays out on its own where something used to be, like
+/// an enemy dying
+#[derive(Clone, Debug, PartialEq)]
+pub struct Remains {
+	pub position: Vec2,
+	pub facing: Vec2,
+	pub tint: Color,
+	pub animator: Animator,
+	/// How long until we're gone, whether or not there's art for the clip
+	pub lifetime: Cooldown,
+}
+
+impl Remains {
+	/// How long to stay for when there's no clip to tell us
+	pub const DEFAULT_LIFETIME: Duration = Duration::from_millis(300);
+
+	pub fn new(clip: &'static str, position: Vec2, tint: Color, lifetime: Duration) -> Self {
+		Self {
+			position,
+			facing: Vec2::ZERO,
+			tint,
+			animator: Animator::new(clip),
+			lifetime: Cooldown::waiting(lifetime),
+		}
+	}
+
+	pub fn with_facing(mut self, facing: Vec2) -> Self {
+		self.facing = facing;
+		self
+	}
+
+	/// Returns false once we're done
+	pub fn tick(&mut self, delta: Duration) -> bool {
+		self.animator.tick(delta);
+		self.lifetime.subtract(delta);
+		!self.lifetime.is_ready()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	fn clip(looping: &str) -> Clip {
+		Clip::parse(&["100", looping, "a", "b", "c"]).unwrap()
+	}
+
+	fn ms(ms: u64) -> Duration {
+		Duration::from_millis(ms)
+	}
+
+	#[test]
+	fn parses_clips() {
+		let clip = clip("loop");
+		assert_eq!(clip.frames, ["a", "b", "c"]);
+		assert_eq!(clip.frame_time, ms(100));
+		assert_eq!(clip.looping, Looping::Loop);
+		assert_eq!(clip.length(), ms(300));
+
+		assert_eq!(
+			Clip::parse(&["100", "once", "a"]).unwrap().looping,
+			Looping::Once
+		);
+		assert_eq!(
+			Clip::parse(&["100", "hold", "a"]).unwrap().looping,
+			Looping::Hold
+		);
+	}
+
+	#[test]
+	fn rejects_bad_clips() {
+		assert_eq!(Clip::parse(&[]), None);
+		assert_eq!(Clip::parse(&["100"]), None);
+		assert_eq!(Clip::parse(&["100", "loop"]), None);
+		assert_eq!(Clip::parse(&["0", "loop", "a"]), None);
+		assert_eq!(Clip::parse(&["fast", "loop", "a"]), None);
+		assert_eq!(Clip::parse(&["100", "bounce", "a"]), None);
+	}
+
+	#[test]
+	fn frames_change_on_the_boundary() {
+		let clip = clip("loop");
+		assert_eq!(clip.frame(ms(0)), Some("a"));
+		assert_eq!(clip.frame(ms(99)), Some("a"));
+		assert_eq!(clip.frame(ms(100)), Some("b"));
+		assert_eq!(clip.frame(ms(299)), Some("c"));
+	}
+
+	#[test]
+	fn looping_starts_over() {
+		let clip = clip("loop");
+		assert_eq!(clip.frame(ms(300)), Some("a"));
+		assert_eq!(clip.frame(ms(1150)), Some("c"));
+		assert!(!clip.is_finished(ms(10_000)));
+	}
+
+	#[test]
+	fn once_ends_with_nothing() {
+		let clip = clip("once");
+		assert_eq!(clip.frame(ms(299)), Some("c"));
+		assert!(!clip.is_finished(ms(299)));
+
+		assert_eq!(clip.frame(ms(300)), None);
+		assert!(clip.is_finished(ms(300)));
+	}
+
+	#[test]
+	fn hold_stays_on_the_last_frame() {
+		let clip = clip("hold");
+		assert_eq!(clip.frame(ms(300)), Some("c"));
+		assert_eq!(clip.frame(ms(10_000)), Some("c"));
+		assert!(clip.is_finished(ms(300)));
+	}
+
+	#[test]
+	fn playing_a_new_clip_starts_it_over() {
+		let mut animator = Animator::new("walk");
+		animator.tick(ms(250));
+
+		animator.play("walk");
+		assert_eq!(animator.elapsed(), ms(250));
+
+		animator.play("shoot");
+		assert_eq!(animator.clip(), "shoot");
+		assert_eq!(animator.elapsed(), Duration::ZERO);
+	}
+}
@@ -0,0 +1,714 @@
+use std::{collections::HashMap, f32::consts::PI, fmt, rc::Rc, time::Duration};
+#[cfg(feature = "sound")]
+use std::{
+	collections::VecDeque,
+	sync::{Arc, Mutex},
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use smitten::Vec2;
+
+use crate::settings::Settings;
+
+pub const SAMPLE_RATE: u32 = 22050;
+/// Sounds this far to one side of the player are all the way in that ear
+const PAN_DISTANCE: f32 = 12.0;
+/// Sounds this far from the player are at half volume
+const FALLOFF_DISTANCE: f32 = 16.0;
+/// Past this many sounds at once, the oldest is cut off for the newest
+pub const MAX_VOICES: usize = 16;
+
+/// Sounds are turned up and down by what they are
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Category {
+	Weapons,
+	/// Hits, deaths and explosions
+	World,
+	/// Pickups and upgrades
+	Interface,
+	/// The player getting hurt
+	Player,
+}
+
+impl Category {
+	pub const ALL: [Category; 4] = [
+		Category::Weapons,
+		Category::World,
+		Category::Interface,
+		Category::Player,
+	];
+}
+
+impl fmt::Display for Category {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Category::Weapons => write!(f, "weapons"),
+			Category::World => write!(f, "world"),
+			Category::Interface => write!(f, "interface"),
+			Category::Player => write!(f, "player"),
+		}
+	}
+}
+
+/// The volume of every category, each from 0 to 1. These are on top of the
+/// main volume.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Volumes {
+	pub weapons: f32,
+	pub world: f32,
+	pub interface: f32,
+	pub player: f32,
+}
+
+impl Volumes {
+	pub fn get(&self, category: Category) -> f32 {
+		match category {
+			Category::Weapons => self.weapons,
+			Category::World => self.world,
+			Category::Interface => self.interface,
+			Category::Player => self.player,
+		}
+	}
+
+	pub fn get_mut(&mut self, category: Category) -> &mut f32 {
+		match category {
+			Category::Weapons => &mut self.weapons,
+			Category::World => &mut self.world,
+			Category::Interface => &mut self.interface,
+			Category::Player => &mut self.player,
+		}
+	}
+}
+
+impl Default for Volumes {
+	fn default() -> Self {
+		Self {
+			weapons: 1.0,
+			world: 1.0,
+			interface: 1.0,
+			player: 1.0,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Sound {
+	/// Takes the name of the weapon that fired
+	Gunshot(&'static str),
+	Hit,
+	EnemyDeath,
+	Explosion,
+	Pickup,
+	Upgrade,
+	PlayerHurt,
+	PlayerDeath,
+}
+
+impl Sound {
+	pub fn category(&self) -> Category {
+		match self {
+			Sound::Gunshot(_) => Category::Weapons,
+			Sound::Hit | Sound::EnemyDeath | Sound::Explosion => Category::World,
+			Sound::Pickup | Sound::Upgrade => Category::Interface,
+			Sound::PlayerHurt | Sound::PlayerDeath => Category::Player,
+		}
+	}
+
+	fn recipe(&self) -> Recipe {
+		let recipe = |waveform, from, to, seconds, gain| Recipe {
+			waveform,
+			from,
+			to,
+			seconds,
+			gain,
+		};
+
+		match self {
+			Sound::Gunshot("Pistol") => recipe(Waveform::Square, 880.0, 220.0, 0.08, 0.4),
+			Sound::Gunshot("Uzi") => recipe(Waveform::Square, 1200.0, 400.0, 0.05, 0.3),
+			Sound::Gunshot("Shotgun") => recipe(Waveform::Noise, 600.0, 100.0, 0.2, 0.6),
+			// Walls, barrels, and anything else that's put down rather than shot
+			Sound::Gunshot(_) => recipe(Waveform::Sine, 140.0, 90.0, 0.1, 0.5),
+			Sound::Hit => recipe(Waveform::Noise, 2000.0, 2000.0, 0.04, 0.25),
+			Sound::EnemyDeath => recipe(Waveform::Square, 300.0, 60.0, 0.25, 0.35),
+			Sound::Explosion => recipe(Waveform::Noise, 200.0, 40.0, 0.6, 0.9),
+			Sound::Pickup => recipe(Waveform::Sine, 660.0, 1320.0, 0.15, 0.4),
+			Sound::Upgrade => recipe(Waveform::Sine, 440.0, 1760.0, 0.4, 0.4),
+			Sound::PlayerHurt => recipe(Waveform::Square, 220.0, 110.0, 0.15, 0.5),
+			Sound::PlayerDeath => recipe(Waveform::Square, 220.0, 30.0, 1.0, 0.6),
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Waveform {
+	Sine,
+	Square,
+	/// Sample and hold noise at the frequency
+	Noise,
+}
+
+/// How to make a sound. The frequency slides from `from` to `to` while the
+/// volume falls off over `seconds`.
+#[derive(Copy, Clone, Debug)]
+struct Recipe {
+	waveform: Waveform,
+	from: f32,
+	to: f32,
+	seconds: f32,
+	gain: f32,
+}
+
+impl Recipe {
+	fn synthesize(&self, sample_rate: u32) -> Vec<f32> {
+		// Seeded so the same sound always comes out the same
+		let mut rng = StdRng::seed_from_u64(0);
+		let length = (self.seconds * sample_rate as f32) as usize;
+
+		let mut phase = 0.0;
+		let mut held = 0.0;
+		let mut samples = Vec::with_capacity(length);
+		for idx in 0..length {
+			let progress = idx as f32 / length as f32;
+			let frequency = self.from + (self.to - self.from) * progress;
+
+			let previous = phase;
+			phase = (phase + frequency / sample_rate as f32) % 1.0;
+
+			let wave = match self.waveform {
+				Waveform::Sine => (phase * 2.0 * PI).sin(),
+				Waveform::Square if phase < 0.5 => 1.0,
+				Waveform::Square => -1.0,
+				Waveform::Noise => {
+					if phase < previous || idx == 0 {
+						held = rng.gen_range(-1.0..1.0);
+					}
+					held
+				}
+			};
+
+			let envelope = (1.0 - progress).powi(2);
+			samples.push(wave * envelope * self.gain);
+		}
+
+		samples
+	}
+}
+
+/// A sound that's playing
+struct Voice {
+	samples: Rc<[f32]>,
+	cursor: usize,
+	category: Category,
+	left: f32,
+	right: f32,
+}
+
+/// Mixes every playing sound in software into interleaved stereo samples
+pub struct Mixer {
+	pub sample_rate: u32,
+	/// From 0 to 1
+	pub volume: f32,
+	pub volumes: Volumes,
+	voices: Vec<Voice>,
+	/// Every sound that's been played, so it's only synthesized once
+	cache: HashMap<Sound, Rc<[f32]>>,
+}
+
+impl Mixer {
+	pub fn new(sample_rate: u32) -> Self {
+		Self {
+			sample_rate,
+			volume: 1.0,
+			volumes: Volumes::default(),
+			voices: vec![],
+			cache: HashMap::new(),
+		}
+	}
+
+	/// Play a sound that doesn't come from anywhere in particular
+	pub fn play(&mut self, sound: Sound) {
+		self.start(sound, 1.0, 0.0);
+	}
+
+	/// Play a sound at `position`, panned and quietened by how far it is
+	/// from the `listener`
+	pub fn play_at(&mut self, sound: Sound, position: Vec2, listener: Vec2) {
+		let pan = ((position.x - listener.x) / PAN_DISTANCE).clamp(-1.0, 1.0);
+		let gain = 1.0 / (1.0 + position.distance_with(listener) / FALLOFF_DISTANCE);
+
+		self.start(sound, gain, pan);
+	}
+
+	fn start(&mut self, sound: Sound, gain: f32, pan: f32) {
+		// Equal power, so a sound moving across doesn't dip in the middle
+		let angle = (pan + 1.0) * PI / 4.0;
+
+		let sample_rate = self.sample_rate;
+		let samples = self
+			.cache
+			.entry(sound)
+			.or_insert_with(|| sound.recipe().synthesize(sample_rate).into())
+			.clone();
+
+		if self.voices.len() >= MAX_VOICES {
+			self.voices.remove(0);
+		}
+
+		self.voices.push(Voice {
+			samples,
+			cursor: 0,
+			category: sound.category(),
+			left: angle.cos() * gain,
+			right: angle.sin() * gain,
+		});
+	}
+
+	/// How many sounds are playing
+	pub fn voices(&self) -> usize {
+		self.voices.len()
+	}
+
+	/// Add every playing sound into `out`, which is interleaved left and
+	/// right, and forget sounds that have finished.
+	pub fn mix(&mut self, out: &mut [f32]) {
+		out.iter_mut().for_each(|s| *s = 0.0);
+
+		for voice in self.voices.iter_mut() {
+			let volume = self.volume * self.volumes.get(voice.category);
+
+			for frame in out.chunks_exact_mut(2) {
+				let sample = match voice.samples.get(voice.cursor) {
+					None => break,
+					Some(sample) => *sample * volume,
+				};
+				voice.cursor += 1;
+
+				frame[0] += sample * voice.left;
+				frame[1] += sample * voice.right;
+			}
+		}
+
+		self.voices.retain(|v| v.cursor < v.samples.len());
+		out.iter_mut().for_each(|s| *s = s.clamp(-1.0, 1.0));
+	}
+
+	/// Mix the next `frames` frames into a new buffer
+	pub fn render(&mut self, frames: usize) -> Vec<f32> {
+		let mut out = vec![0.0; frames * 2];
+		self.mix(&mut out);
+		out
+	}
+}
+
+/// Somewhere for mixed samples to go
+pub trait Output {
+	/// Takes interleaved stereo samples
+	fn write(&mut self, samples: &[f32]);
+
+	/// What the samples given to [Output::write] should be mixed at
+	fn sample_rate(&self) -> u32 {
+		SAMPLE_RATE
+	}
+}
+
+/// Drops everything. Used when there's no sound device.
+pub struct Silent;
+
+impl Output for Silent {
+	fn write(&mut self, _samples: &[f32]) {}
+}
+
+/// The default sound device. The game mixes ahead a tick at a time and the
+/// device takes from the queue as it needs to.
+#[cfg(feature = "sound")]
+pub struct Device {
+	queue: Arc<Mutex<VecDeque<f32>>>,
+	sample_rate: u32,
+	// Sound stops when this is dropped
+	_stream: cpal::Stream,
+}
+
+#[cfg(feature = "sound")]
+impl Device {
+	/// How far behind the game the device can fall before we skip ahead, in
+	/// seconds. Long frames would otherwise leave sounds playing late.
+	const BACKLOG: f32 = 0.1;
+
+	/// The default output device, if there is one we can use
+	pub fn open() -> Option<Self> {
+		use cpal::{
+			traits::{DeviceTrait, HostTrait, StreamTrait},
+			SampleFormat,
+		};
+
+		let device = cpal::default_host().default_output_device()?;
+		let config = match device.default_output_config() {
+			Ok(config) => config,
+			Err(e) => {
+				eprintln!("Couldn't get the sound device's config: {e}");
+				return None;
+			}
+		};
+
+		let sample_rate = config.sample_rate().0;
+		let format = config.sample_format();
+		let config = config.into();
+		let queue = Arc::new(Mutex::new(VecDeque::new()));
+
+		let stream = match format {
+			SampleFormat::F32 => Self::stream::<f32>(&device, &config, queue.clone()),
+			SampleFormat::I16 => Self::stream::<i16>(&device, &config, queue.clone()),
+			SampleFormat::U16 => Self::stream::<u16>(&device, &config, queue.clone()),
+			format => {
+				eprintln!("Sound device wants {format} samples, which we can't make");
+				return None;
+			}
+		}?;
+
+		if let Err(e) = stream.play() {
+			eprintln!("Couldn't start sound: {e}");
+			return None;
+		}
+
+		Some(Self {
+			queue,
+			sample_rate,
+			_stream: stream,
+		})
+	}
+
+	fn stream<T>(
+		device: &cpal::Device,
+		config: &cpal::StreamConfig,
+		queue: Arc<Mutex<VecDeque<f32>>>,
+	) -> Option<cpal::Stream>
+	where
+		T: cpal::SizedSample + cpal::FromSample<f32>,
+	{
+		use cpal::traits::DeviceTrait;
+
+		let channels = config.channels as usize;
+		let callback = move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+			let mut queue = queue.lock().unwrap();
+
+			for frame in data.chunks_mut(channels) {
+				let left = queue.pop_front().unwrap_or(0.0);
+				let right = queue.pop_front().unwrap_or(0.0);
+
+				// Mono gets both sides, anything past stereo gets nothing
+				for (idx, sample) in frame.iter_mut().enumerate() {
+					let value = match (channels, idx) {
+						(1, _) => (left + right) / 2.0,
+						(_, 0) => left,
+						(_, 1) => right,
+						_ => 0.0,
+					};
+					*sample = T::from_sample(value);
+				}
+			}
+		};
+
+		let error = |e| eprintln!("Sound device error: {e}");
+		match device.build_output_stream(config, callback, error, None) {
+			Ok(stream) => Some(stream),
+			Err(e) => {
+				eprintln!("Couldn't open the sound device: {e}");
+				None
+			}
+		}
+	}
+}
+
+#[cfg(feature = "sound")]
+impl Output for Device {
+	fn write(&mut self, samples: &[f32]) {
+		let mut queue = self.queue.lock().unwrap();
+		queue.extend(samples);
+
+		let backlog = (self.sample_rate as f32 * Self::BACKLOG) as usize * 2;
+		if queue.len() > backlog {
+			let skip = queue.len() - backlog;
+			queue.drain(..skip);
+		}
+	}
+
+	fn sample_rate(&self) -> u32 {
+		self.sample_rate
+	}
+}
+
+/// The game's sound. Keeps the mixer running in step with the game and hands
+/// what it mixes to an [Output].
+pub struct Audio {
+	pub mixer: Mixer,
+	output: Box<dyn Output>,
+	/// Fractions of a frame left over from the last tick
+	owed: f64,
+}
+
+impl Audio {
+	pub fn new(output: Box<dyn Output>) -> Self {
+		Self {
+			mixer: Mixer::new(output.sample_rate()),
+			output,
+			owed: 0.0,
+		}
+	}
+
+	/// Sound through the default device, or none at all if there isn't one
+	pub fn device() -> Self {
+		#[cfg(feature = "sound")]
+		if let Some(device) = Device::open() {
+			return Self::new(Box::new(device));
+		}
+
+		eprintln!("No sound device, playing silently");
+		Self::default()
+	}
+
+	pub fn play(&mut self, sound: Sound) {
+		self.mixer.play(sound);
+	}
+
+	pub fn play_at(&mut self, sound: Sound, position: Vec2, listener: Vec2) {
+		self.mixer.play_at(sound, position, listener);
+	}
+
+	pub fn tick(&mut self, delta: Duration, settings: &Settings) {
+		self.mixer.volume = settings.volume;
+		self.mixer.volumes = settings.volumes;
+
+		self.owed += delta.as_secs_f64() * self.mixer.sample_rate as f64;
+		let frames = self.owed as usize;
+		self.owed -= frames as f64;
+
+		if frames > 0 {
+			let samples = self.mixer.render(frames);
+			self.output.write(&samples);
+		}
+	}
+}
+
+impl Default for Audio {
+	fn default() -> Self {
+		Self::new(Box::new(Silent))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::{
+		cell::RefCell,
+		io::{self, Write},
+	};
+
+	use super::*;
+	use crate::{status::StatusEffect, Achievements, Atlas, Game, HighScores, Raster, Screen};
+
+	/// Keeps everything written to it. Clones share what's been kept.
+	#[derive(Clone, Default)]
+	struct Recording(Rc<RefCell<Vec<f32>>>);
+
+	impl Output for Recording {
+		fn write(&mut self, samples: &[f32]) {
+			self.0.borrow_mut().extend_from_slice(samples);
+		}
+	}
+
+	/// Write interleaved stereo samples as a 16-bit PCM WAV
+	fn write_wav<W: Write>(mut w: W, sample_rate: u32, samples: &[f32]) -> io::Result<()> {
+		let channels = 2u16;
+		let bytes_per_sample = 2u16;
+		let data_len = (samples.len() * bytes_per_sample as usize) as u32;
+
+		w.write_all(b"RIFF")?;
+		w.write_all(&(36 + data_len).to_le_bytes())?;
+		w.write_all(b"WAVE")?;
+
+		w.write_all(b"fmt ")?;
+		w.write_all(&16u32.to_le_bytes())?;
+		// PCM
+		w.write_all(&1u16.to_le_bytes())?;
+		w.write_all(&channels.to_le_bytes())?;
+		w.write_all(&sample_rate.to_le_bytes())?;
+		w.write_all(&(sample_rate * (channels * bytes_per_sample) as u32).to_le_bytes())?;
+		w.write_all(&(channels * bytes_per_sample).to_le_bytes())?;
+		w.write_all(&(bytes_per_sample * 8).to_le_bytes())?;
+
+		w.write_all(b"data")?;
+		w.write_all(&data_len.to_le_bytes())?;
+		for sample in samples {
+			let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+			w.write_all(&sample.to_le_bytes())?;
+		}
+
+		Ok(())
+	}
+
+	/// Sum of squares of the left and right channels
+	fn energy(samples: &[f32]) -> (f32, f32) {
+		samples.chunks_exact(2).fold((0.0, 0.0), |(l, r), frame| {
+			(l + frame[0] * frame[0], r + frame[1] * frame[1])
+		})
+	}
+
+	/// Half a second of every sound the game makes, one after another
+	fn script(mixer: &mut Mixer) -> Vec<f32> {
+		let listener = Vec2::new(0.0, 0.0);
+		let sounds = [
+			Sound::Gunshot("Pistol"),
+			Sound::Gunshot("Uzi"),
+			Sound::Gunshot("Shotgun"),
+			Sound::Gunshot("Walls"),
+			Sound::Hit,
+			Sound::EnemyDeath,
+			Sound::Explosion,
+			Sound::Pickup,
+			Sound::Upgrade,
+			Sound::PlayerHurt,
+			Sound::PlayerDeath,
+		];
+
+		let mut out = vec![];
+		for sound in sounds {
+			mixer.play_at(sound, Vec2::new(4.0, 0.0), listener);
+			out.extend(mixer.render(mixer.sample_rate as usize / 2));
+		}
+		out
+	}
+
+	#[test]
+	fn renders_a_wav() {
+		let mut mixer = Mixer::new(SAMPLE_RATE);
+		let samples = script(&mut mixer);
+
+		let path = std::env::temp_dir().join("roundhead-audio-test.wav");
+		write_wav(std::fs::File::create(&path).unwrap(), SAMPLE_RATE, &samples).unwrap();
+		let wav = std::fs::read(&path).unwrap();
+
+		assert_eq!(&wav[0..4], b"RIFF");
+		assert_eq!(&wav[8..12], b"WAVE");
+		assert_eq!(wav.len(), 44 + samples.len() * 2);
+		assert!(wav[44..].iter().any(|b| *b != 0));
+	}
+
+	#[test]
+	fn mixing_is_deterministic() {
+		let first = script(&mut Mixer::new(SAMPLE_RATE));
+		let second = script(&mut Mixer::new(SAMPLE_RATE));
+		assert_eq!(first, second);
+	}
+
+	#[test]
+	fn pans_toward_the_sound() {
+		let mut mixer = Mixer::new(SAMPLE_RATE);
+		let listener = Vec2::new(0.0, 0.0);
+
+		mixer.play_at(Sound::Explosion, Vec2::new(8.0, 0.0), listener);
+		let (left, right) = energy(&mixer.render(SAMPLE_RATE as usize));
+		assert!(right > left * 2.0);
+
+		mixer.play_at(Sound::Explosion, Vec2::new(-8.0, 0.0), listener);
+		let (left, right) = energy(&mixer.render(SAMPLE_RATE as usize));
+		assert!(left > right * 2.0);
+	}
+
+	#[test]
+	fn farther_is_quieter() {
+		let listener = Vec2::new(0.0, 0.0);
+
+		let mut near = Mixer::new(SAMPLE_RATE);
+		near.play_at(Sound::Hit, Vec2::new(0.0, 2.0), listener);
+		let mut far = Mixer::new(SAMPLE_RATE);
+		far.play_at(Sound::Hit, Vec2::new(0.0, 40.0), listener);
+
+		let near = energy(&near.render(SAMPLE_RATE as usize));
+		let far = energy(&far.render(SAMPLE_RATE as usize));
+		assert!(near.0 > far.0 && near.1 > far.1);
+	}
+
+	#[test]
+	fn muted_category_is_silent() {
+		let mut mixer = Mixer::new(SAMPLE_RATE);
+		mixer.volumes.weapons = 0.0;
+
+		mixer.play(Sound::Gunshot("Shotgun"));
+		assert!(mixer.render(SAMPLE_RATE as usize).iter().all(|s| *s == 0.0));
+
+		mixer.play(Sound::Pickup);
+		assert!(mixer.render(SAMPLE_RATE as usize).iter().any(|s| *s != 0.0));
+	}
+
+	#[test]
+	fn death_sound_plays_out() {
+		let recording = Recording::default();
+		let mut game = Game::new(
+			Box::new(Raster::new((64, 48))),
+			Settings::default(),
+			HighScores::default(),
+			Achievements::default(),
+			Audio::new(Box::new(recording.clone())),
+			Screen::new((64, 48)),
+			Atlas::empty(),
+		);
+
+		// Any burn at all finishes us on the next tick
+		game.player.health = f32::MIN_POSITIVE;
+		game.player
+			.effects
+			.apply(StatusEffect::burn(10.0, Duration::from_secs(1)));
+
+		// Like the main loop, which keeps mixing after the game stops ticking
+		let frame = Duration::from_millis(100);
+		for _ in 0..5 {
+			game.tick();
+			game.audio.tick(frame, &game.settings);
+		}
+		assert!(game.player.health <= 0.0);
+
+		let samples = recording.0.borrow();
+		let per_frame = SAMPLE_RATE as usize / 10 * 2;
+		assert_eq!(samples.len(), per_frame * 5);
+		assert!(samples[per_frame * 4..].iter().any(|s| *s != 0.0));
+	}
+
+	#[test]
+	fn finished_sounds_are_dropped() {
+		let mut mixer = Mixer::new(SAMPLE_RATE);
+		mixer.play(Sound::Hit);
+		assert_eq!(mixer.voices(), 1);
+
+		mixer.render(SAMPLE_RATE as usize);
+		assert_eq!(mixer.voices(), 0);
+	}
+
+	#[test]
+	fn sounds_are_synthesized_once() {
+		let mut mixer = Mixer::new(SAMPLE_RATE);
+		mixer.play(Sound::Explosion);
+		mixer.play(Sound::Explosion);
+		mixer.play(Sound::Pickup);
+
+		assert_eq!(mixer.cache.len(), 2);
+		assert!(Rc::ptr_eq(
+			&mixer.voices[0].samples,
+			&mixer.voices[1].samples
+		));
+	}
+
+	#[test]
+	fn oldest_voice_is_cut_off() {
+		let mut mixer = Mixer::new(SAMPLE_RATE);
+		mixer.play(Sound::Pickup);
+		for _ in 0..MAX_VOICES {
+			mixer.play(Sound::Explosion);
+		}
+
+		assert_eq!(mixer.voices(), MAX_VOICES);
+		assert!(mixer
+			.voices
+			.iter()
+			.all(|voice| voice.category == Category::World));
+	}
+}
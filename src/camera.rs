@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use smitten::Vec2;
+
+use crate::screen::Screen;
+
+/// Where we're looking from and how closely. World drawing goes through
+/// [Camera::to_screen] and [Camera::scale] so nothing else has to know.
+pub struct Camera {
+	pub position: Vec2,
+	/// World-to-pixel scale on top of what the screen's scaling gives us.
+	/// 2.0 shows everything twice as big.
+	pub zoom: f32,
+	screen: Screen,
+	/// Half the width and height of the area the camera isn't allowed to see
+	/// past, centered on the origin
+	pub bounds: Option<Vec2>,
+	/// From 0 to 1. Shake goes with the square of this.
+	trauma: f32,
+	shake: Vec2,
+	rng: StdRng,
+}
+
+impl Camera {
+	/// How quickly we catch up to what we're following. Higher is snappier.
+	const FOLLOW_RATE: f32 = 8.0;
+	/// Furthest the shake moves us, in world units
+	const MAX_SHAKE: f32 = 0.6;
+	/// Trauma lost per second
+	const TRAUMA_DECAY: f32 = 1.5;
+	pub const MIN_ZOOM: f32 = 0.5;
+	pub const MAX_ZOOM: f32 = 2.0;
+
+	pub fn new(position: Vec2, screen: Screen, seed: u64) -> Self {
+		Self {
+			position,
+			zoom: 1.0,
+			screen,
+			bounds: None,
+			trauma: 0.0,
+			shake: Vec2::ZERO,
+			rng: StdRng::seed_from_u64(seed),
+		}
+	}
+
+	/// Shake from `seed` instead of the one we were made with
+	pub fn reseed(&mut self, seed: u64) {
+		self.rng = StdRng::seed_from_u64(seed);
+	}
+
+	pub fn with_bounds(mut self, half_extents: Vec2) -> Self {
+		self.bounds = Some(half_extents);
+		self.clamp();
+		self
+	}
+
+	pub fn set_zoom(&mut self, zoom: f32) {
+		self.zoom = zoom.clamp(Self::MIN_ZOOM, Self::MAX_ZOOM);
+		self.clamp();
+	}
+
+	/// The window changed size or scaling
+	pub fn set_screen(&mut self, screen: Screen) {
+		self.screen = screen;
+		self.clamp();
+	}
+
+	/// MUR on screen to a world unit
+	pub fn scale_factor(&self) -> f32 {
+		self.zoom * self.screen.world_scale()
+	}
+
+	/// Half the width and height of what's on screen, in world units
+	pub fn view(&self) -> Vec2 {
+		self.screen.murs() / 2.0 / self.scale_factor()
+	}
+
+	/// Ease toward the middle of every target
+	pub fn follow(&mut self, targets: &[Vec2], delta: Duration) {
+		if targets.is_empty() {
+			return;
+		}
+
+		let sum = targets.iter().fold(Vec2::ZERO, |sum, target| sum + *target);
+		let center = sum / targets.len() as f32;
+
+		// Framerate independent exponential smoothing
+		let t = 1.0 - (-Self::FOLLOW_RATE * delta.as_secs_f32()).exp();
+		self.position = self.position + (center - self.position) * t;
+		self.clamp();
+
+		self.trauma = (self.trauma - Self::TRAUMA_DECAY * delta.as_secs_f32()).max(0.0);
+		let strength = Self::MAX_SHAKE * self.trauma * self.trauma;
+		self.shake = Vec2::new(
+			self.rng.gen_range(-1.0..=1.0) * strength,
+			self.rng.gen_range(-1.0..=1.0) * strength,
+		);
+	}
+
+	pub fn add_trauma(&mut self, amount: f32) {
+		self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+	}
+
+	/// Keep the view inside the bounds. If the bounds are smaller than the
+	/// view on an axis we just center on it.
+	fn clamp(&mut self) {
+		let bounds = match self.bounds {
+			None => return,
+			Some(bounds) => bounds,
+		};
+		let view = self.view();
+
+		let axis = |position: f32, bound: f32, view: f32| {
+			let room = bound - view;
+			if room <= 0.0 {
+				0.0
+			} else {
+				position.clamp(-room, room)
+			}
+		};
+
+		self.position = Vec2::new(
+			axis(self.position.x, bounds.x, view.x),
+			axis(self.position.y, bounds.y, view.y),
+		);
+	}
+
+	/// Where a point in the world is on screen, in MUR from the middle
+	pub fn to_screen(&self, world: Vec2) -> Vec2 {
+		(world - self.position - self.shake) * self.scale_factor()
+	}
+
+	/// A world size on screen
+	pub fn scale(&self, world: Vec2) -> Vec2 {
+		world * self.scale_factor()
+	}
+
+	/// A length in pixels at a zoom of 1 to what it is now
+	pub fn pixels(&self, pixels: f32) -> u32 {
+		(pixels * self.scale_factor()).round().max(1.0) as u32
+	}
+}
@@ -0,0 +1,72 @@
+use std::time::Duration;
+
+use smitten::{HorizontalAnchor, Vec2, VerticalAnchor};
+
+use crate::{util::Cooldown, Game};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Style {
+	Hit,
+	/// A hit that did more than the bullet's damage because what it hit was
+	/// vulnerable
+	Crit,
+	Explosion,
+}
+
+/// Combat text that rises up off whatever got hurt and fades out
+#[derive(Clone, Debug)]
+pub struct FloatingText {
+	pub position: Vec2,
+	pub amount: f32,
+	pub style: Style,
+	lifetime: Cooldown,
+}
+
+impl FloatingText {
+	const LIFETIME: Duration = Duration::from_millis(800);
+	/// How far we rise over our life, in world units
+	const RISE: f32 = 1.0;
+
+	pub fn new(position: Vec2, amount: f32, style: Style) -> Self {
+		Self {
+			position,
+			amount,
+			style,
+			lifetime: Cooldown::waiting(Self::LIFETIME),
+		}
+	}
+
+	/// Returns false once we've faded all the way
+	pub fn tick(&mut self, delta: Duration) -> bool {
+		self.lifetime.subtract(delta);
+		!self.lifetime.is_ready()
+	}
+
+	pub fn draw(&self, game: &Game) {
+		let percent = self.lifetime.percent();
+		let screen = game
+			.camera
+			.to_screen(self.position + Vec2::new(0.0, 0.5 + Self::RISE * percent));
+
+		let (text, mut color, scale) = match self.style {
+			Style::Hit => (format!("{:.0}", self.amount), game.theme.damage_hit, 0.4),
+			Style::Crit => (format!("{:.0}!", self.amount), game.theme.damage_crit, 0.55),
+			Style::Explosion => (
+				format!("{:.0}", self.amount),
+				game.theme.damage_explosion,
+				0.5,
+			),
+		};
+		color.a = 1.0 - percent * percent;
+
+		game.render.write(
+			&text,
+			(
+				HorizontalAnchor::Center(screen.x),
+				VerticalAnchor::Center(screen.y),
+			),
+			color,
+			scale * game.camera.scale_factor(),
+		);
+	}
+}
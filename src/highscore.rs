@@ -0,0 +1,190 @@
+use std::{
+	fs,
+	io::{self, Write},
+	path::{Path, PathBuf},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// The first line of the scores file. Bump the number if the format changes.
+const HEADER: &str = "roundhead-scores";
+const VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+	pub name: String,
+	pub score: f32,
+	pub wave: u32,
+	pub duration: Duration,
+	pub seed: u64,
+	pub mode: String,
+	/// Seconds since the unix epoch
+	pub date: u64,
+}
+
+impl Run {
+	pub fn now(name: String, score: f32, wave: u32, duration: Duration, seed: u64) -> Self {
+		let date = SystemTime::now()
+			.duration_since(UNIX_EPOCH)
+			.map(|d| d.as_secs())
+			.unwrap_or(0);
+
+		Self {
+			name: clean_name(&name),
+			score,
+			wave,
+			duration,
+			seed,
+			mode: String::from(crate::GAME_MODE),
+			date,
+		}
+	}
+
+	fn to_line(&self) -> String {
+		format!(
+			"{}\t{}\t{}\t{}\t{}\t{}\t{}",
+			self.name,
+			self.score,
+			self.wave,
+			self.duration.as_secs_f32(),
+			self.seed,
+			self.mode,
+			self.date
+		)
+	}
+
+	fn from_line(line: &str) -> Option<Self> {
+		let mut parts = line.split('\t');
+
+		Some(Self {
+			name: parts.next()?.to_owned(),
+			score: parts.next()?.parse().ok()?,
+			wave: parts.next()?.parse().ok()?,
+			duration: Duration::try_from_secs_f32(parts.next()?.parse().ok()?).ok()?,
+			seed: parts.next()?.parse().ok()?,
+			mode: parts.next()?.to_owned(),
+			date: parts.next()?.parse().ok()?,
+		})
+	}
+}
+
+/// Tabs and newlines would break the file
+fn clean_name(name: &str) -> String {
+	let name: String = name
+		.chars()
+		.filter(|c| !c.is_control())
+		.take(HighScores::NAME_LENGTH)
+		.collect();
+
+	if name.is_empty() {
+		String::from("anonymous")
+	} else {
+		name
+	}
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HighScores {
+	runs: Vec<Run>,
+}
+
+impl HighScores {
+	/// How many runs we keep
+	pub const KEEP: usize = 10;
+	pub const NAME_LENGTH: usize = 12;
+
+	pub fn runs(&self) -> &[Run] {
+		&self.runs
+	}
+
+	/// Would a run with this score make it onto the table?
+	pub fn qualifies(&self, score: f32) -> bool {
+		self.runs.len() < Self::KEEP
+			|| self
+				.runs
+				.last()
+				.map(|run| score > run.score)
+				.unwrap_or(true)
+	}
+
+	pub fn insert(&mut self, run: Run) {
+		let idx = self
+			.runs
+			.iter()
+			.position(|other| run.score > other.score)
+			.unwrap_or(self.runs.len());
+
+		self.runs.insert(idx, run);
+		self.runs.truncate(Self::KEEP);
+	}
+
+	fn path() -> Option<PathBuf> {
+		data_dir().map(|dir| dir.join("roundhead").join("scores"))
+	}
+
+	/// An empty table if there's no file or we can't make sense of it
+	pub fn load() -> Self {
+		let contents = match Self::path().map(fs::read_to_string) {
+			Some(Ok(contents)) => contents,
+			_ => return Self::default(),
+		};
+
+		let header = format!("{HEADER} {VERSION}");
+		let mut lines = contents.lines();
+		if lines.next() != Some(header.as_str()) {
+			eprintln!("Scores file has an unknown version, ignoring it");
+			return Self::default();
+		}
+
+		let mut scores = Self::default();
+		lines
+			.filter_map(Run::from_line)
+			.for_each(|run| scores.insert(run));
+		scores
+	}
+
+	pub fn save(&self) -> io::Result<()> {
+		let path = Self::path()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+
+		let mut contents = format!("{HEADER} {VERSION}\n");
+		for run in &self.runs {
+			contents.push_str(&run.to_line());
+			contents.push('\n');
+		}
+
+		write_atomically(&path, &contents)
+	}
+}
+
+/// Write to a temporary file and move it over the real one so a crash part
+/// way through can't leave a half written file.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+	if let Some(parent) = path.parent() {
+		fs::create_dir_all(parent)?;
+	}
+
+	let temporary = path.with_extension("tmp");
+	{
+		let mut file = fs::File::create(&temporary)?;
+		file.write_all(contents.as_bytes())?;
+		file.sync_all()?;
+	}
+
+	fs::rename(temporary, path)
+}
+
+/// Where programs keep their data on this platform
+pub fn data_dir() -> Option<PathBuf> {
+	let env = |key: &str| std::env::var_os(key).map(PathBuf::from);
+
+	// Tests shouldn't touch the player's real scores and achievements
+	if cfg!(test) {
+		None
+	} else if cfg!(windows) {
+		env("APPDATA")
+	} else if cfg!(target_os = "macos") {
+		env("HOME").map(|home| home.join("Library").join("Application Support"))
+	} else {
+		env("XDG_DATA_HOME").or_else(|| env("HOME").map(|home| home.join(".local").join("share")))
+	}
+}
@@ -0,0 +1,405 @@
+use std::fs;
+
+use smitten::{Color, HorizontalAnchor, Vec2, VerticalAnchor};
+
+use crate::{
+	highscore,
+	theme::{self, Theme},
+	weapon::Ammunition,
+	Game,
+};
+
+/// Built from `hud.txt` in the repo root. See there for the format.
+const DEFAULT_LAYOUT: &str = include_str!("../hud.txt");
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Kind {
+	Text,
+	Bar,
+	Marker,
+	Messages,
+}
+
+/// Game state a widget can show
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Binding {
+	None,
+	Score,
+	Multiplier,
+	MultiplierTime,
+	Weapon,
+	Ammo,
+	Wave,
+	WaveTime,
+	Health,
+}
+
+impl Binding {
+	fn parse(name: &str) -> Option<Self> {
+		Some(match name {
+			"score" => Binding::Score,
+			"multiplier" => Binding::Multiplier,
+			"multiplier_time" => Binding::MultiplierTime,
+			"weapon" => Binding::Weapon,
+			"ammo" => Binding::Ammo,
+			"wave" => Binding::Wave,
+			"wave_time" => Binding::WaveTime,
+			"health" => Binding::Health,
+			_ => return None,
+		})
+	}
+
+	fn text(&self, game: &Game) -> String {
+		match self {
+			Binding::None => String::new(),
+			Binding::Score => format!("{}", game.score),
+			Binding::Multiplier => format!("x{}", game.score_multiplier.current as usize),
+			Binding::Weapon => game.player.weapon().name().to_owned(),
+			Binding::Ammo => match game.player.weapon().ammo() {
+				Ammunition::Limited { rounds, .. } => format!("{}", rounds),
+				_ => String::new(),
+			},
+			Binding::Wave => format!("wave {}", game.stats.waves),
+			Binding::Health => format!("{:.0}", game.player.health.max(0.0)),
+			Binding::MultiplierTime | Binding::WaveTime => {
+				format!("{:.0}%", self.fill(game) * 100.0)
+			}
+		}
+	}
+
+	/// How full a bar bound to this is, from 0 to 1. Unbound bars are full.
+	fn fill(&self, game: &Game) -> f32 {
+		let fill = match self {
+			Binding::None => 1.0,
+			Binding::MultiplierTime => 1.0 - game.score_multiplier.percent(),
+			Binding::WaveTime => 1.0 - game.wave_timer.percent(),
+			Binding::Health => game.player.health / Game::PLAYER_HEALTH_MAX,
+			_ => 0.0,
+		};
+
+		fill.clamp(0.0, 1.0)
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Horizontal {
+	Left,
+	Center,
+	Right,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Vertical {
+	Top,
+	Center,
+	Bottom,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Anchor {
+	horizontal: Horizontal,
+	vertical: Vertical,
+	/// Centered on the player rather than the screen, so it moves with them
+	player: bool,
+}
+
+impl Anchor {
+	fn parse(s: &str) -> Option<Self> {
+		let mut anchor = Anchor {
+			horizontal: Horizontal::Center,
+			vertical: Vertical::Center,
+			player: false,
+		};
+
+		if s == "player" {
+			anchor.player = true;
+			return Some(anchor);
+		}
+
+		for part in s.split('-') {
+			match part {
+				"top" => anchor.vertical = Vertical::Top,
+				"bottom" => anchor.vertical = Vertical::Bottom,
+				"left" => anchor.horizontal = Horizontal::Left,
+				"right" => anchor.horizontal = Horizontal::Right,
+				"center" => (),
+				_ => return None,
+			}
+		}
+
+		Some(anchor)
+	}
+
+	fn at(&self, game: &Game, offset: Vec2) -> (HorizontalAnchor, VerticalAnchor) {
+		let offset = if self.player {
+			offset + game.camera.to_screen(game.player.position)
+		} else {
+			offset
+		};
+
+		let horizontal = match self.horizontal {
+			Horizontal::Left => HorizontalAnchor::Left(offset.x),
+			Horizontal::Center => HorizontalAnchor::Center(offset.x),
+			Horizontal::Right => HorizontalAnchor::Right(offset.x),
+		};
+
+		let vertical = match self.vertical {
+			Vertical::Top => VerticalAnchor::Top(offset.y),
+			Vertical::Center => VerticalAnchor::Center(offset.y),
+			Vertical::Bottom => VerticalAnchor::Bottom(offset.y),
+		};
+
+		(horizontal, vertical)
+	}
+}
+
+/// A color that's either given outright or looked up in the theme when drawn
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Paint {
+	Fixed(Color),
+	Themed(&'static str),
+}
+
+impl Paint {
+	fn parse(value: &str) -> Option<Self> {
+		match Theme::NAMES.iter().find(|name| **name == value) {
+			Some(name) => Some(Paint::Themed(name)),
+			None => parse_color(value).map(Paint::Fixed),
+		}
+	}
+
+	fn color(&self, theme: &Theme) -> Color {
+		match self {
+			Paint::Fixed(color) => *color,
+			Paint::Themed(name) => theme.get(name).unwrap_or(Color::BLACK),
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Widget {
+	kind: Kind,
+	bind: Binding,
+	anchor: Anchor,
+	offset: Vec2,
+	/// A width of None is the width of the screen
+	size: (Option<f32>, f32),
+	scale: f32,
+	color: Paint,
+	background: Option<Paint>,
+	padding: f32,
+	track: f32,
+	line: f32,
+}
+
+impl Widget {
+	fn parse(line: &str) -> Option<Self> {
+		let mut words = line.split_whitespace();
+		let kind = match words.next()? {
+			"text" => Kind::Text,
+			"bar" => Kind::Bar,
+			"marker" => Kind::Marker,
+			"messages" => Kind::Messages,
+			_ => return None,
+		};
+
+		let mut widget = Widget {
+			kind,
+			bind: Binding::None,
+			anchor: Anchor::parse("center")?,
+			offset: Vec2::ZERO,
+			size: (Some(1.0), 1.0),
+			scale: 0.5,
+			color: Paint::Themed("text"),
+			background: None,
+			padding: 0.0,
+			track: 0.0,
+			line: 0.5,
+		};
+
+		for word in words {
+			let (key, value) = word.split_once('=')?;
+
+			match key {
+				"bind" => widget.bind = Binding::parse(value)?,
+				"anchor" => widget.anchor = Anchor::parse(value)?,
+				"offset" => {
+					let (x, y) = pair(value)?;
+					widget.offset = Vec2::new(x.parse().ok()?, y.parse().ok()?);
+				}
+				"size" => {
+					let (w, h) = pair(value)?;
+					let w = match w {
+						"full" => None,
+						w => Some(w.parse().ok()?),
+					};
+					widget.size = (w, h.parse().ok()?);
+				}
+				"scale" => widget.scale = value.parse().ok()?,
+				"color" => widget.color = Paint::parse(value)?,
+				"background" => widget.background = Some(Paint::parse(value)?),
+				"padding" => widget.padding = value.parse().ok()?,
+				"track" => widget.track = value.parse().ok()?,
+				"line" => widget.line = value.parse().ok()?,
+				_ => return None,
+			}
+		}
+
+		Some(widget)
+	}
+
+	fn size(&self, game: &Game) -> Vec2 {
+		let width = self.size.0.unwrap_or(game.screen.murs().x);
+		Vec2::new(width, self.size.1)
+	}
+
+	fn draw(&self, game: &Game) {
+		let color = self.color.color(&game.theme);
+
+		match self.kind {
+			Kind::Text => {
+				let text = self.bind.text(game);
+				if !text.is_empty() {
+					game.render
+						.write(&text, self.anchor.at(game, self.offset), color, self.scale);
+				}
+			}
+			Kind::Bar => self.draw_bar(game),
+			Kind::Marker => {
+				// The track is centered on the offset, whatever the anchor
+				let along = self.track * (self.bind.fill(game) - 0.5);
+				game.render.anchored_rect(
+					self.anchor.at(game, self.offset + Vec2::new(along, 0.0)),
+					self.size(game),
+					color,
+				);
+			}
+			Kind::Messages => self.draw_messages(game),
+		}
+	}
+
+	fn draw_bar(&self, game: &Game) {
+		let size = self.size(game);
+		if let Some(background) = self.background {
+			game.render.anchored_rect(
+				self.anchor.at(game, self.offset),
+				size,
+				background.color(&game.theme),
+			);
+		}
+
+		// The fill sits inside the padding and grows from the left, or from
+		// the right if we're anchored there
+		let inner = size - Vec2::new(self.padding * 2.0, self.padding * 2.0);
+		let fill = Vec2::new(inner.x * self.bind.fill(game), inner.y);
+
+		let x = match self.anchor.horizontal {
+			Horizontal::Left => self.offset.x + self.padding,
+			Horizontal::Center => self.offset.x - (inner.x - fill.x) / 2.0,
+			Horizontal::Right => self.offset.x - self.padding,
+		};
+		let y = match self.anchor.vertical {
+			Vertical::Top => self.offset.y - self.padding,
+			Vertical::Center => self.offset.y,
+			Vertical::Bottom => self.offset.y + self.padding,
+		};
+
+		game.render.anchored_rect(
+			self.anchor.at(game, Vec2::new(x, y)),
+			fill,
+			self.color.color(&game.theme),
+		);
+	}
+
+	fn draw_messages(&self, game: &Game) {
+		if !game.settings.show_messages || game.messages.is_empty() {
+			return;
+		}
+
+		if let Some(background) = self.background {
+			let height = game.messages.len() as f32 * self.line + self.size.1 + 0.25;
+			game.render.anchored_rect(
+				self.anchor.at(game, self.offset),
+				Vec2::new(self.size(game).x, height),
+				background.color(&game.theme),
+			);
+		}
+
+		// Lines go down from a top anchor and up from anything else
+		let step = match self.anchor.vertical {
+			Vertical::Top => -self.line,
+			_ => self.line,
+		};
+
+		for (idx, msg) in game.messages.iter().enumerate() {
+			game.render.write(
+				&msg.message,
+				self.anchor
+					.at(game, self.offset + Vec2::new(0.0, step * idx as f32)),
+				msg.color(),
+				self.scale,
+			)
+		}
+	}
+}
+
+fn pair(value: &str) -> Option<(&str, &str)> {
+	value.split_once(',').map(|(a, b)| (a.trim(), b.trim()))
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+	Some(match value {
+		"black" => Color::BLACK,
+		"white" => Color::WHITE,
+		"red" => Color::RED,
+		"green" => Color::GREEN,
+		"blue" => Color::BLUE,
+		"yellow" => Color::YELLOW,
+		"grey" => Color::grey(0.5),
+		other => theme::parse_color(other)?,
+	})
+}
+
+/// Every widget on the HUD
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hud {
+	widgets: Vec<Widget>,
+}
+
+impl Hud {
+	/// The layout from the data directory if there is one, otherwise the built
+	/// in one
+	pub fn load() -> Self {
+		let custom = highscore::data_dir()
+			.map(|dir| dir.join("roundhead").join("hud.txt"))
+			.and_then(|path| fs::read_to_string(path).ok());
+
+		match custom {
+			Some(layout) => Self::parse(&layout),
+			None => Self::parse(DEFAULT_LAYOUT),
+		}
+	}
+
+	pub fn parse(layout: &str) -> Self {
+		let widgets = layout
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.filter_map(|line| {
+				let parsed = Widget::parse(line);
+				if parsed.is_none() {
+					eprintln!("Couldn't understand HUD widget: {line}");
+				}
+				parsed
+			})
+			.collect();
+
+		Self { widgets }
+	}
+
+	pub fn draw(&self, game: &Game) {
+		for widget in &self.widgets {
+			widget.draw(game);
+		}
+	}
+}
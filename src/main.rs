@@ -1,387 +1,695 @@
 #![feature(drain_filter)]
 
+mod achievement;
+mod animation;
+mod audio;
+mod camera;
+mod floating;
+mod highscore;
+mod hud;
+mod menu;
+mod minimap;
+mod overlay;
+mod particle;
+mod physics;
+mod raster;
+mod render;
+mod screen;
+mod settings;
+#[cfg(test)]
+mod snapshot;
+mod sprite;
+mod state;
+mod stats;
+mod status;
+mod theme;
 mod thing;
 mod traits;
 mod util;
 mod weapon;
 
-use rand::{thread_rng, Rng};
-use thing::{Enemy, Pickup};
+use achievement::{Achievements, Values};
+use animation::{Animator, Remains};
+use audio::{Audio, Sound};
+use camera::Camera;
+use floating::{FloatingText, Style};
+use highscore::HighScores;
+use hud::Hud;
+use overlay::Overlay;
+use particle::{Emitter, Particles};
+use physics::{Body, Physical};
+use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+use raster::Raster;
+use render::{Render, Window};
+use screen::Screen;
+use settings::Settings;
+use sprite::Atlas;
+use state::{State, Transition};
+use stats::{DamageSource, KillSource, Stats};
+use status::{Affected, Effects, StatusEffect};
+use theme::{Theme, ThemeChoice};
+use thing::{Enemy, Hazard, Pickup};
 use traits::{Colideable, Destructible, Explosive, Hittable};
 use util::Cooldown;
-use weapon::{Ammunition, Bullet, Pistol, Shotgun, Uzi, Weapon};
+use weapon::{Bullet, Pistol, Shotgun, Uzi, Weapon};
 
 use std::{
 	collections::VecDeque,
+	fs, io,
 	ops::{Add, Mul, Sub},
+	path::PathBuf,
 	time::{Duration, Instant},
 };
 
-use smitten::{
-	Color, Draw, FontId, HorizontalAnchor, Key, SignedDistance, Smitten, SmittenEvent, Vec2,
-	VerticalAnchor,
-};
+use smitten::{Color, Draw, Key, SignedDistance, Smitten, SmittenEvent, Vec2};
 
-const TURQUOISE: Color = Color::rgb8(0x33, 0xaa, 0x88);
-const PURPLE: Color = Color::rgb(0.9, 0.8, 0.85);
 const MUR: u32 = 48;
+/// What size the window opens at. It can be resized after.
 const DIM: (u32, u32) = (1280, 960);
+/// Recorded with high scores. There's only the one for now.
+const GAME_MODE: &str = "rooms";
 
 fn main() {
+	let mut args = std::env::args().skip(1);
+	if args.next().as_deref() == Some("--screenshot") {
+		let path = args
+			.next()
+			.unwrap_or_else(|| String::from("screenshot.png"));
+		if let Err(e) = screenshot(&path) {
+			eprintln!("Couldn't save a screenshot to {path}: {e}");
+		}
+		return;
+	}
+
 	let mut smitty = Smitten::new(DIM, "Roundhead", MUR);
 
 	let font = smitty.make_font("Hack-Regular.ttf");
-	smitty.clear_color(Color::grey(0.5));
-
-	let mut game = Game {
-		smitten: smitty,
-		player: Player::default(),
-		bullets: vec![],
-		enemies: vec![Enemy {
-			position: Vec2::new(0.0, 5.0),
-			color: PURPLE,
-			health: 1.0,
-			speed: 0.1,
-			cooldown: Cooldown::waiting(Duration::from_secs(1000)),
-			should_move_next_frame: true,
-		}],
-		last_render: Instant::now(),
-		score: 0.0,
-		score_multiplier: Multiplier::default(),
-		walls: vec![],
-		barrels: vec![],
-		explosions: vec![],
-		wave_count: 3,
-		wave_timer: Cooldown::ready(Duration::from_secs_f32(10.0)),
-		font,
-		pickups: Game::pickup_locations()
-			.into_iter()
-			.map(|position| Pickup { position })
-			.collect(),
-		possible_pickups: vec![],
-		pickup_respawn: Cooldown::waiting(Duration::from_secs(5)),
-		messages: VecDeque::with_capacity(10),
-		upgrades: Upgrade::upgrade_list(),
-		paused: false,
-	};
+	let atlas = Atlas::load(&mut smitty);
+	let window = Window::new(smitty);
+
+	let mut game = Game::new(
+		Box::new(window.canvas(font)),
+		Settings::default(),
+		HighScores::load(),
+		Achievements::load(),
+		Audio::device(),
+		Screen::new(DIM),
+		atlas,
+	)
+	.with_window(window.clone());
+	let mut state = State::Title;
+	let mut last_frame = Instant::now();
 
 	loop {
-		let events = game.smitten.events();
+		let events = window.events();
+		game.update_screen(&events);
+		game.update_theme();
+
+		match state.frame(&mut game, &events) {
+			Transition::Stay => (),
+			Transition::To(next) => state = next,
+			Transition::Restart => {
+				game = game.restart();
+				state = State::Playing;
+			}
+			Transition::Quit => break,
+		}
+
+		// Sound plays out on every screen, and after we've died, not just
+		// while the game is ticking
+		let now = Instant::now();
+		game.audio.tick(now - last_frame, &game.settings);
+		last_frame = now;
+
+		// Draw
+		game.render.clear();
+		state.draw(&game);
+		window.swap();
+		game.overlay.frame();
+	}
+}
+
+/// Draw the first frame of a game without a window and save it as a PNG. For
+/// checking the game still draws somewhere without a GPU, like CI. The seed is
+/// always the same so the picture is too.
+fn screenshot(path: &str) -> io::Result<()> {
+	let raster = Raster::new(DIM);
+	let mut game = Game::new(
+		Box::new(raster.clone()),
+		Settings::default(),
+		HighScores::load(),
+		Achievements::load(),
+		Audio::default(),
+		Screen::new(DIM),
+		Atlas::empty(),
+	)
+	.with_seed(0);
+
+	game.render.clear();
+	State::Playing.draw(&game);
+	raster.save_png(path)
+}
+
+// A higher level struct so I can keep a player.position et al.
+struct Game {
+	render: Box<dyn Render>,
+	/// Where input comes from. There isn't one when we're drawing off screen.
+	window: Option<Window>,
+	player: Player,
+	bullets: Vec<Bullet>,
+	enemies: Vec<Enemy>,
+	last_render: Instant,
+	score_multiplier: Multiplier,
+	score: f32,
+	walls: Vec<thing::Wall>,
+	barrels: Vec<thing::Barrel>,
+	explosions: Vec<Explosion>,
+	particles: Particles,
+	/// Enemies dying and barrels going off
+	remains: Vec<Remains>,
+	screen: Screen,
+	atlas: Atlas,
+	hud: Hud,
+	floating: Vec<FloatingText>,
+	camera: Camera,
+	overlay: Overlay,
+	theme: Theme,
+	/// What `theme` was loaded as, so we notice it changing in the settings
+	theme_choice: ThemeChoice,
+	hazards: Vec<Hazard>,
+	wave_timer: Cooldown,
+	pickups: Vec<Pickup>,
+	possible_pickups: Vec<AmmoPickup>,
+	pickup_respawn: Cooldown,
+	messages: VecDeque<Alert>,
+	upgrades: VecDeque<Upgrade>,
+	settings: Settings,
+	scores: HighScores,
+	achievements: Achievements,
+	audio: Audio,
+	/// Everything random in a run comes from `rng`, so the same seed plays
+	/// out the same way
+	seed: u64,
+	rng: StdRng,
+	stats: Stats,
+}
+
+impl Game {
+	const BULLET_LIFESPAN: Duration = Duration::from_secs(1);
+	const BULLET_SPEED: f32 = 40.0;
+	const BULLET_KNOCKBACK: f32 = 3.0;
+	const PLAYER_LENGTH: f32 = 0.75;
+	const PLAYER_DIM: Vec2 = Vec2::new(Game::PLAYER_LENGTH, Game::PLAYER_LENGTH);
+	const PLAYER_HEALTH_MAX: f32 = 30.0;
+	/// Health per second while regenerating
+	const PLAYER_REGENERATION: f32 = 1.0;
+
+	pub fn new(
+		mut render: Box<dyn Render>,
+		settings: Settings,
+		scores: HighScores,
+		achievements: Achievements,
+		audio: Audio,
+		screen: Screen,
+		atlas: Atlas,
+	) -> Self {
+		let seed = thread_rng().gen();
+		let theme = Theme::load(settings.theme);
+		render.clear_color(theme.background);
+		let pickup_locations = Game::pickup_locations();
+
+		Game {
+			render,
+			window: None,
+			player: Player::default(),
+			bullets: vec![],
+			enemies: vec![Enemy {
+				position: Vec2::new(0.0, 5.0),
+				color: theme.bonus_enemy,
+				health: 1.0,
+				max_health: 1.0,
+				speed: 0.1,
+				cooldown: Cooldown::waiting(Duration::from_secs(1000)),
+				should_move_next_frame: true,
+				body: Body::new(Enemy::BONUS_MASS),
+				effects: Effects::default(),
+				last_hit: None,
+				animation: Animator::new("enemy_walk"),
+				bonus: true,
+			}],
+			last_render: Instant::now(),
+			score: 0.0,
+			score_multiplier: Multiplier::default(),
+			walls: vec![],
+			barrels: vec![],
+			explosions: vec![],
+			particles: Particles::new(seed),
+			remains: vec![],
+			screen,
+			atlas,
+			hud: Hud::load(),
+			floating: vec![],
+			camera: Camera::new(Vec2::ZERO, screen, seed)
+				.with_bounds(Vec2::new(Game::ROOM_WIDTH / 2.0, Game::ROOM_HEIGHT / 2.0)),
+			theme_choice: settings.theme,
+			theme,
+			hazards: vec![],
+			wave_timer: Cooldown::ready(Duration::from_secs_f32(10.0)),
+			pickups: pickup_locations
+				.iter()
+				.map(|position| Pickup {
+					position: *position,
+				})
+				.collect(),
+			overlay: Overlay::new(pickup_locations),
+			possible_pickups: vec![AmmoPickup::Health],
+			pickup_respawn: Cooldown::waiting(Duration::from_secs(5)),
+			messages: VecDeque::with_capacity(10),
+			upgrades: Upgrade::upgrade_list(),
+			settings,
+			scores,
+			achievements,
+			audio,
+			seed,
+			rng: StdRng::seed_from_u64(seed),
+			stats: Stats::default(),
+		}
+	}
+
+	/// Write this run's stats out as JSON for poking at later. Returns where
+	/// they went.
+	pub fn export_stats(&self) -> io::Result<PathBuf> {
+		let dir = highscore::data_dir()
+			.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?
+			.join("roundhead")
+			.join("runs");
+		fs::create_dir_all(&dir)?;
+
+		let path = dir.join(format!("{}.json", self.seed));
+		let json = format!(
+			"{{\"seed\":{},\"mode\":\"{}\",\"score\":{},\"wave\":{},\"stats\":{}}}",
+			self.seed,
+			GAME_MODE,
+			self.score,
+			self.stats.waves,
+			self.stats.to_json()
+		);
+		fs::write(&path, json)?;
+
+		Ok(path)
+	}
+
+	/// Play out from `seed` instead of a random one
+	pub fn with_window(mut self, window: Window) -> Self {
+		self.window = Some(window);
+		self
+	}
+
+	pub fn with_seed(mut self, seed: u64) -> Self {
+		self.seed = seed;
+		self.rng = StdRng::seed_from_u64(seed);
+		self.particles = Particles::new(seed);
+		self.camera.reseed(seed);
+		self
+	}
+
+	/// A fresh game that keeps the things that should outlast a run
+	pub fn restart(self) -> Self {
+		let game = Game::new(
+			self.render,
+			self.settings,
+			self.scores,
+			self.achievements,
+			self.audio,
+			self.screen,
+			self.atlas,
+		);
+
+		match self.window {
+			Some(window) => game.with_window(window),
+			None => game,
+		}
+	}
+
+	/// Nothing is ever held down without a window
+	pub fn is_key_down(&self, key: Key) -> bool {
+		self.window
+			.as_ref()
+			.map(|window| window.is_key_down(key))
+			.unwrap_or(false)
+	}
+
+	/// Handle input while we're playing
+	pub fn play(&mut self, events: &[SmittenEvent]) {
+		let bindings = self.settings.bindings;
 
 		events.iter().for_each(|e| match e {
 			SmittenEvent::Keydown { key, .. } => match key {
-				Some(Key::Q) => {
-					game.player.decrement_weapon();
+				Some(k) if *k == bindings.previous_weapon => {
+					self.player.decrement_weapon();
 				}
-				Some(Key::E) => {
-					game.player.increment_weapon();
+				Some(k) if *k == bindings.next_weapon => {
+					self.player.increment_weapon();
 				}
 				Some(Key::Row1) => {
-					game.player.select_weapon(0);
+					self.player.select_weapon(0);
 				}
 				Some(Key::Row2) => {
-					game.player.select_weapon(1);
+					self.player.select_weapon(1);
 				}
 				Some(Key::Row3) => {
-					game.player.select_weapon(2);
+					self.player.select_weapon(2);
 				}
 				Some(Key::Row4) => {
-					game.player.select_weapon(3);
+					self.player.select_weapon(3);
 				}
 				Some(Key::Row5) => {
-					game.player.select_weapon(4);
-				}
-				_ => (),
-			},
-			SmittenEvent::Keyup { key, .. } => match key {
-				Some(Key::P) => {
-					if game.paused {
-						game.paused = false;
-					} else {
-						game.paused = true;
-					}
+					self.player.select_weapon(4);
 				}
 				_ => (),
 			},
 			_ => (),
 		});
 
-		if game.smitten.is_key_down(Key::Escape) {
-			break;
-		}
+		let stunned = self.player.effects.is_stunned();
 
-		if game.smitten.is_key_down(Key::Space) {
-			if !game.player.must_release_shoot {
-				game.shoot();
+		if self.is_key_down(bindings.fire) {
+			if !self.player.must_release_shoot && !stunned {
+				self.shoot();
 			}
-		} else if game.player.must_release_shoot {
-			game.player.must_release_shoot = false;
+		} else if self.player.must_release_shoot {
+			self.player.must_release_shoot = false;
+		}
+
+		if self.settings.developer && self.is_key_down(Key::K) {
+			self.score += 1.0;
 		}
 
-		if game.smitten.is_key_down(Key::P) {
-			game.score += 1.0;
+		if self.settings.developer {
+			if self.is_key_down(Key::Z) {
+				self.camera.set_zoom(self.camera.zoom * 1.01);
+			} else if self.is_key_down(Key::X) {
+				self.camera.set_zoom(self.camera.zoom / 1.01);
+			}
 		}
 
 		let mut movec = Vec2::ZERO;
-		if game.smitten.is_key_down(Key::W) {
+		if self.is_key_down(bindings.up) {
 			movec += Vec2::new(0.0, 1.0);
-		} else if game.smitten.is_key_down(Key::S) {
+		} else if self.is_key_down(bindings.down) {
 			movec -= Vec2::new(0.0, 1.0);
 		}
 
-		if game.smitten.is_key_down(Key::A) {
+		if self.is_key_down(bindings.left) {
 			movec -= Vec2::new(1.0, 0.0);
-		} else if game.smitten.is_key_down(Key::D) {
+		} else if self.is_key_down(bindings.right) {
 			movec += Vec2::new(1.0, 0.0);
 		}
 
-		movec = movec.normalize_correct() * (1.25 / 32.0);
-		game.player.position += movec;
+		movec = movec.normalize_correct() * (1.25 / 32.0) * self.player.effects.speed_scale();
+		self.player.position += movec;
+		self.player.moving = movec != Vec2::ZERO;
 		if movec != Vec2::ZERO {
-			game.player.facing = movec.normalize_correct();
+			self.player.facing = movec.normalize_correct();
 		}
+	}
 
-		game.tick();
+	/// Keep up with the window changing size, and with fullscreen and scaling
+	/// being changed in the settings
+	pub fn update_screen(&mut self, events: &[SmittenEvent]) {
+		let mut screen = self.screen;
+
+		for event in events {
+			match event {
+				SmittenEvent::Resized { width, height } => screen.resize(*width, *height),
+				SmittenEvent::Keyup {
+					key: Some(Key::F11),
+					..
+				} => self.settings.fullscreen = !self.settings.fullscreen,
+				_ => (),
+			}
+		}
 
-		// Draw
-		game.smitten.clear();
-		game.draw();
-		game.smitten.swap();
+		if self.settings.fullscreen != screen.fullscreen {
+			if let Some(window) = &self.window {
+				window.set_fullscreen(self.settings.fullscreen);
+			}
+			screen.fullscreen = self.settings.fullscreen;
+		}
+		screen.scaling = self.settings.scaling;
+
+		if screen != self.screen {
+			self.screen = screen;
+			self.camera.set_screen(screen);
+		}
 	}
-}
 
-// A higher level struct so I can keep a player.position et al.
-struct Game {
-	smitten: Smitten,
-	player: Player,
-	bullets: Vec<Bullet>,
-	enemies: Vec<Enemy>,
-	last_render: Instant,
-	score_multiplier: Multiplier,
-	score: f32,
-	walls: Vec<thing::Wall>,
-	barrels: Vec<thing::Barrel>,
-	explosions: Vec<Explosion>,
-	wave_count: usize,
-	wave_timer: Cooldown,
-	font: FontId,
-	pickups: Vec<Pickup>,
-	possible_pickups: Vec<AmmoPickup>,
-	pickup_respawn: Cooldown,
-	messages: VecDeque<Alert>,
-	upgrades: VecDeque<Upgrade>,
-	paused: bool,
-}
+	/// Load the theme again if it was changed in the settings, recoloring
+	/// enemies that are already out
+	pub fn update_theme(&mut self) {
+		if self.settings.theme == self.theme_choice {
+			return;
+		}
 
-impl Game {
-	const BULLET_LIFESPAN: Duration = Duration::from_secs(1);
-	const BULLET_SPEED: f32 = 40.0;
-	const PLAYER_LENGTH: f32 = 0.75;
-	const PLAYER_DIM: Vec2 = Vec2::new(Game::PLAYER_LENGTH, Game::PLAYER_LENGTH);
-	const PLAYER_HEALTH_MAX: f32 = 30.0;
+		let old = self.theme;
+		self.theme = Theme::load(self.settings.theme);
+		self.theme_choice = self.settings.theme;
+		self.render.clear_color(self.theme.background);
+
+		for enemy in self.enemies.iter_mut() {
+			if enemy.bonus {
+				enemy.color = self.theme.bonus_enemy;
+			} else if enemy.color == old.enemy {
+				enemy.color = self.theme.enemy;
+			}
+		}
+	}
+
+	/// Keep time from passing while we're not ticking, so we don't get one
+	/// huge tick when we start again
+	pub fn freeze(&mut self) {
+		self.last_render = Instant::now();
+	}
 
 	pub fn rect<P: Into<Vec2>, D: Into<Vec2>, R: Into<Draw>>(&self, pos: P, dim: D, draw: R) {
-		self.smitten
-			.rect(pos.into() - self.player.position, dim, draw)
+		self.render.rect(
+			self.camera.to_screen(pos.into()),
+			self.camera.scale(dim.into()),
+			draw.into(),
+		)
 	}
 
 	pub fn draw(&self) {
 		self.draw_grid();
 
 		for bullet in &self.bullets {
-			self.smitten.sdf(SignedDistance::Circle {
-				center: bullet.position - self.player.position,
-				radius: 2,
-				color: Color::rgb(1.0, 0.0, 0.0),
+			if self.atlas.draw(
+				self,
+				"bullet",
+				bullet.position,
+				bullet.velocity,
+				self.theme.bullet,
+			) {
+				continue;
+			}
+
+			self.render.sdf(SignedDistance::Circle {
+				center: self.camera.to_screen(bullet.position),
+				radius: self.camera.pixels(2.0),
+				color: self.theme.bullet,
 			})
 		}
 
 		for wall in &self.walls {
-			self.smitten.sdf(SignedDistance::Circle {
-				center: wall.position - self.player.position,
-				radius: MUR / 2,
-				color: wall.damage_color(),
+			let color = wall.damage_color(&self.theme);
+			if self
+				.atlas
+				.draw(self, "wall", wall.position, Vec2::ZERO, color)
+			{
+				continue;
+			}
+
+			self.render.sdf(SignedDistance::Circle {
+				center: self.camera.to_screen(wall.position),
+				radius: self.camera.pixels(MUR as f32 / 2.0),
+				color,
 			})
 		}
 
 		for barrel in &self.barrels {
-			self.smitten.sdf(SignedDistance::Circle {
-				center: barrel.position - self.player.position,
-				radius: MUR / 2,
-				color: Color::rgb8(235, 147, 25),
+			if self
+				.atlas
+				.draw(self, "barrel", barrel.position, Vec2::ZERO, Color::WHITE)
+			{
+				continue;
+			}
+
+			self.render.sdf(SignedDistance::Circle {
+				center: self.camera.to_screen(barrel.position),
+				radius: self.camera.pixels(MUR as f32 / 2.0),
+				color: self.theme.barrel,
+			})
+		}
+
+		for hazard in &self.hazards {
+			let mut color = hazard.effect.kind.tint(&self.theme);
+			color.a = 0.25 * (1.0 - hazard.lifetime.percent());
+
+			self.render.sdf(SignedDistance::Circle {
+				center: self.camera.to_screen(hazard.position),
+				radius: self.camera.pixels(hazard.radius * MUR as f32),
+				color,
 			})
 		}
 
 		for pickup in &self.pickups {
-			self.rect(pickup.position, Game::PLAYER_DIM / 2.0, Color::RED);
+			if !self
+				.atlas
+				.draw(self, "pickup", pickup.position, Vec2::ZERO, Color::WHITE)
+			{
+				self.rect(pickup.position, Game::PLAYER_DIM / 2.0, self.theme.pickup);
+			}
 		}
 
 		for enemy in &self.enemies {
-			self.rect(enemy.position, Game::PLAYER_DIM, enemy.color)
-			/*self.smitten.sdf(SignedDistance::Circle {
+			// They're always walking toward us
+			let facing = self.player.position - enemy.position;
+			let tint = enemy.effects().tint(enemy.color, &self.theme);
+			if self
+				.atlas
+				.draw_animated(self, &enemy.animation, enemy.position, facing, tint)
+				|| self.atlas.draw(self, "enemy", enemy.position, facing, tint)
+			{
+				continue;
+			}
+
+			self.rect(enemy.position, Game::PLAYER_DIM, tint)
+			/*self.render.sdf(SignedDistance::Circle {
 				center: enemy.position - self.player.position,
 				radius: (Game::PLAYER_LENGTH * MUR as f32 / 2.0).floor() as u32,
 				color: enemy.color,
 			})*/
 		}
 
-		// Draw us. We're not affected by player.position movement
-		self.smitten.sdf(SignedDistance::LineSegment {
-			start: Vec2::new(0.0, 0.0),
-			end: self.player.facing * 0.5,
-			thickness: 2,
-			color: Color::BLACK,
-		});
-		self.smitten.sdf(SignedDistance::Circle {
-			center: Vec2::new(0.0, 0.0),
-			radius: (Game::PLAYER_LENGTH * MUR as f32 / 2.0).floor() as u32,
-			color: TURQUOISE,
-		});
+		for remains in &self.remains {
+			let drawn = self.atlas.draw_animated(
+				self,
+				&remains.animator,
+				remains.position,
+				remains.facing,
+				remains.tint,
+			);
 
-		for explosion in &self.explosions {
-			self.smitten.sdf(SignedDistance::Circle {
-				center: explosion.position - self.player.position,
-				radius: (explosion.starting_radius
-					+ explosion.ending_radius * explosion.cooldown.percent())
-				.round() as u32,
-				color: Color::rgba(1.0, 0.8, 0.4, 0.3),
-			})
+			// Without art an enemy shrinks away
+			if !drawn && remains.animator.clip() == "enemy_die" {
+				let left = 1.0 - remains.lifetime.percent();
+				let mut tint = remains.tint;
+				tint.a *= left;
+				self.rect(remains.position, Game::PLAYER_DIM * left, tint);
+			}
 		}
 
-		self.draw_walls();
-		self.draw_ui();
-	}
-
-	const WAVE_TIMER_HEIGHT: f32 = 0.5;
-
-	fn draw_ui(&self) {
-		let ghost = Color::rgba(0.0, 0.0, 0.0, 0.3);
-
-		// Score
-		self.smitten.write(
-			self.font,
-			&format!("{}", self.score),
-			(HorizontalAnchor::Center(0.0), VerticalAnchor::Top(-0.75)),
-			Color::BLACK,
-			1.0,
-		);
+		// Draw us
+		self.draw_player();
 
-		// Multiplier
-		let width = 3.0 * (1.0 - self.score_multiplier.percent());
-		self.smitten.anchored_rect(
-			(HorizontalAnchor::Center(0.0), VerticalAnchor::Top(-2.25)),
-			(3.0, 0.1),
-			Color::BLACK,
-		);
+		for explosion in &self.explosions {
+			self.render.sdf(SignedDistance::Circle {
+				center: self.camera.to_screen(explosion.position),
+				radius: self.camera.pixels(
+					explosion.starting_radius
+						+ explosion.ending_radius * explosion.cooldown.percent(),
+				),
+				color: self.theme.explosion,
+			})
+		}
 
-		self.smitten.anchored_rect(
-			(
-				HorizontalAnchor::Center(width - 1.5),
-				VerticalAnchor::Top(-2.25 + 0.175),
-			),
-			(0.175, 0.5),
-			Color::BLACK,
-		);
+		self.particles.draw(self);
 
-		self.smitten.write(
-			self.font,
-			format!("x{}", self.score_multiplier.current as usize),
-			(HorizontalAnchor::Center(-1.25), VerticalAnchor::Top(-2.75)),
-			Color::BLACK,
-			0.5,
-		);
+		if self.settings.show_health_bars {
+			self.draw_health_bars();
+		}
 
-		// Wave timer
-		self.smitten.anchored_rect(
-			(HorizontalAnchor::Left(0.0), VerticalAnchor::Top(0.0)),
-			(DIM.0 as f32 / MUR as f32, Game::WAVE_TIMER_HEIGHT),
-			ghost,
-		);
+		if self.settings.show_damage_numbers {
+			for floating in &self.floating {
+				floating.draw(self);
+			}
+		}
 
-		self.smitten.anchored_rect(
-			(HorizontalAnchor::Left(0.0), VerticalAnchor::Top(0.0)),
-			(
-				(DIM.0 as f32 / MUR as f32) * (1.0 - self.wave_timer.percent()),
-				Game::WAVE_TIMER_HEIGHT,
-			),
-			Color::BLUE,
-		);
+		self.draw_walls();
+		minimap::draw_indicators(self);
+		self.draw_ui();
 
-		// Message box
-		if !self.messages.is_empty() {
-			self.smitten.anchored_rect(
-				(
-					HorizontalAnchor::Right(0.0),
-					VerticalAnchor::Top(-Game::WAVE_TIMER_HEIGHT),
-				),
-				(5.0, self.messages.len() as f32 / 1.5 + 0.25),
-				ghost,
-			);
-			self.write_messages();
+		if self.settings.debug_overlay {
+			overlay::draw(self);
 		}
+	}
 
-		// Weapon details
-		self.smitten.write(
-			self.font,
-			self.player.weapon().name(),
-			(0.0, VerticalAnchor::Bottom(1.0)),
-			Color::BLACK,
-			0.5,
-		);
-
-		if let Ammunition::Limited { rounds, .. } = self.player.weapon().ammo() {
-			self.smitten.write(
-				self.font,
-				format!("{}", rounds),
-				(0.0, VerticalAnchor::Center(-1.0)),
-				Color::BLACK,
-				0.5,
-			);
+	fn draw_player(&self) {
+		// Sprites are drawn in white and grey so they take the theme's color
+		// like the shapes do
+		let color = if self.player.is_flashing() {
+			self.theme.player_flash
+		} else {
+			self.player.effects().tint(self.theme.player, &self.theme)
+		};
+		let position = self.player.position;
+		let facing = self.player.facing;
+		if self
+			.atlas
+			.draw_animated(self, &self.player.animation, position, facing, color)
+			|| self.atlas.draw(self, "player", position, facing, color)
+		{
+			return;
 		}
 
-		// Health
-		self.smitten
-			.anchored_rect((0.0, 1.0), (2.0, 0.4), Color::rgba(0.0, 0.0, 0.0, 0.5));
+		let center = self.camera.to_screen(position);
+		self.render.sdf(SignedDistance::LineSegment {
+			start: center,
+			end: self
+				.camera
+				.to_screen(self.player.position + self.player.facing * 0.5),
+			thickness: self.camera.pixels(2.0),
+			color: self.theme.player_facing,
+		});
+		self.render.sdf(SignedDistance::Circle {
+			center,
+			radius: self
+				.camera
+				.pixels((Game::PLAYER_LENGTH * MUR as f32 / 2.0).floor()),
+			color,
+		});
+	}
 
-		self.smitten.anchored_rect(
-			(
-				-(Self::PLAYER_HEALTH_MAX - self.player.health) / Self::PLAYER_HEALTH_MAX,
-				1.0,
-			),
-			(1.8 * (self.player.health / Self::PLAYER_HEALTH_MAX), 0.2),
-			Color::rgb(0.0, 0.75, 0.0),
-		);
+	/// A small bar over everything that's been hurt
+	fn draw_health_bars(&self) {
+		let enemies = self
+			.enemies
+			.iter()
+			.map(|e| (e.position, e.health / e.max_health));
+		let walls = self
+			.walls
+			.iter()
+			.map(|w| (w.position, w.health / thing::Wall::WALL_HEALTH));
 
-		if self.player.health <= 0.0 {
-			self.smitten.anchored_rect(
-				(0.0, 0.0),
-				(DIM.0 as f32 / MUR as f32, 2.0),
-				Color::rgba(0.0, 0.0, 0.0, 0.5),
-			);
+		let width = 0.8;
+		for (position, percent) in enemies.chain(walls) {
+			if percent >= 1.0 || percent <= 0.0 {
+				continue;
+			}
 
-			self.smitten.write(
-				self.font,
-				String::from("You died!"),
-				(0.0, VerticalAnchor::Center(0.0)),
-				Color::rgb(0.6, 0.0, 0.0),
-				1.5,
+			let above = position + Vec2::new(0.0, 0.6);
+			self.rect(above, (width, 0.12), self.theme.health_back);
+			self.rect(
+				above - Vec2::new(width * (1.0 - percent) / 2.0, 0.0),
+				(width * percent, 0.08),
+				color_lerp(self.theme.health, self.theme.health_low, percent),
 			);
 		}
 	}
 
-	fn write_messages(&self) {
-		for (idx, msg) in self.messages.iter().enumerate() {
-			self.smitten.write(
-				self.font,
-				&msg.message,
-				(
-					HorizontalAnchor::Right(0.0),
-					VerticalAnchor::Top(-Game::WAVE_TIMER_HEIGHT - idx as f32 / 1.5),
-				),
-				msg.color(),
-				0.5,
-			)
-		}
+	fn draw_ui(&self) {
+		self.hud.draw(self);
+		minimap::draw(self);
 	}
 
 	fn reap<T, F>(vec: &mut Vec<T>, f: F) -> Vec<T>
@@ -414,71 +722,171 @@ impl Game {
 		self.last_render = now;
 		let dsec = delta.as_secs_f64();
 
-		if self.paused || self.player.health <= 0.0 {
+		if self.player.health <= 0.0 {
 			return;
 		}
+		self.stats.alive += delta;
 
 		self.explosions
 			.iter_mut()
 			.for_each(|expl| expl.cooldown.subtract(delta));
 		Self::reap(&mut self.explosions, |e| e.cooldown.is_ready());
+		self.particles.tick(delta);
+		self.floating.retain_mut(|f| f.tick(delta));
 
 		self.wave_things(delta);
+		self.tick_hazards(delta);
 
 		self.bullets = self
 			.bullets
 			.drain_filter(|bul| now.duration_since(bul.birth) < Game::BULLET_LIFESPAN)
 			.collect();
 
-		self.bullets
-			.iter_mut()
-			.for_each(|bul| bul.position += bul.velocity * dsec as f32);
+		let hits = self.move_bullets(dsec as f32);
 
+		self.player.integrate(delta);
 		Self::collide_walls(&mut self.player);
 		self.walls.iter().for_each(|wall| {
-			colide_and_move(wall, &mut self.player);
+			physics::collide_static(wall, &mut self.player);
 		});
 		self.barrels.iter().for_each(|wall| {
-			colide_and_move(wall, &mut self.player);
+			physics::collide_static(wall, &mut self.player);
 		});
-		self.player.tick(delta);
+		self.camera.follow(&[self.player.position], delta);
+
+		let burned = self.player.tick(delta);
+		self.stats.took(DamageSource::Burn, burned);
+		if self.settings.regeneration && self.enemies.is_empty() {
+			self.player
+				.heal(Game::PLAYER_REGENERATION * delta.as_secs_f32());
+		}
 		self.check_pickups();
 		self.do_pickup_respawn(delta);
 
-		let _hits = Self::do_bullet_hits(
-			&mut self.enemies,
-			&mut self.bullets,
-			Some(self.player.position),
-		);
+		for (idx, bullet) in hits {
+			let pushback = bullet.velocity.normalize_correct() * Game::BULLET_KNOCKBACK;
+			self.enemies[idx].body.apply_impulse(pushback);
+		}
 		Self::burry_dead(&mut self.enemies)
 			.into_iter()
 			.for_each(|e| self.enemy_killed(e));
 		self.tick_enemies(delta);
 
-		let _wall_hits = Self::do_bullet_hits(&mut self.walls, &mut self.bullets, None);
-		Self::burry_dead(&mut self.walls);
+		let destroyed = Self::burry_dead(&mut self.walls);
+		self.stats.walls_destroyed += destroyed.len() as u32;
+		for wall in destroyed {
+			self.particles
+				.emit(&Emitter::debris(&self.theme), wall.position, None);
+			self.hazards.push(Hazard::rubble(wall.position));
+		}
 
-		let _barrel_hits = Self::do_bullet_hits(&mut self.barrels, &mut self.bullets, None);
+		self.barrels
+			.iter_mut()
+			.for_each(|barrel| barrel.tick(delta));
 		let barrels = Self::burry_dead(&mut self.barrels);
+		if let Some(length) = self.atlas.clip_length("barrel_explode") {
+			for barrel in &barrels {
+				self.remains.push(Remains::new(
+					"barrel_explode",
+					barrel.position,
+					Color::WHITE,
+					length,
+				));
+			}
+		}
 		self.explode(barrels);
 
+		self.animate(delta);
+
 		// Messages
 		self.messages.retain_mut(|a| {
 			a.lifetime.subtract(delta);
 			!a.lifetime.is_ready()
 		});
 		self.score_multiplier.subtract(delta);
+
+		if self.player.health <= 0.0 {
+			self.audio.play(Sound::PlayerDeath);
+		} else if std::mem::take(&mut self.player.hurt) {
+			self.audio.play(Sound::PlayerHurt);
+		}
+
+		self.check_achievements();
+		self.overlay.tick_time = now.elapsed();
+	}
+
+	fn check_achievements(&mut self) {
+		let values = Values {
+			stats: &self.stats,
+			score: self.score,
+		};
+
+		for achievement in self.achievements.check(&values) {
+			self.push_alert(Alert::with_color(
+				format!("achievement: {}", achievement.title),
+				self.theme.achievement_alert,
+			));
+		}
+	}
+
+	/// Pick what everything should be playing and move it along
+	fn animate(&mut self, delta: Duration) {
+		// Shooting is started when a shot's fired and plays out from there
+		let player = &mut self.player;
+		let shooting =
+			player.animation.clip() == "player_shoot" && !self.atlas.is_finished(&player.animation);
+		let clip = if !player.invulnerable.is_ready() {
+			"player_hurt"
+		} else if shooting {
+			"player_shoot"
+		} else if player.moving {
+			"player_walk"
+		} else {
+			"player_idle"
+		};
+		player.animation.play(clip);
+		player.animation.tick(delta);
+
+		for enemy in self.enemies.iter_mut() {
+			if enemy.animation.clip() == "enemy_attack" && self.atlas.is_finished(&enemy.animation)
+			{
+				enemy.animation.play("enemy_walk");
+			}
+			enemy.animation.tick(delta);
+		}
+
+		self.remains.retain_mut(|remains| remains.tick(delta));
 	}
 
 	fn enemy_killed(&mut self, e: Enemy) {
+		let length = self
+			.atlas
+			.clip_length("enemy_die")
+			.unwrap_or(Remains::DEFAULT_LIFETIME);
+		self.remains.push(
+			Remains::new(
+				"enemy_die",
+				e.position,
+				e.effects().tint(e.color, &self.theme),
+				length,
+			)
+			.with_facing(self.player.position - e.position),
+		);
+
 		self.score += 100.0 * self.score_multiplier.current;
 		self.score_multiplier.increment();
-
-		if e.color == PURPLE {
+		self.stats.killed(e.last_hit);
+		self.stats.multiplier(self.score_multiplier.current);
+		self.audio
+			.play_at(Sound::EnemyDeath, e.position, self.player.position);
+		self.particles
+			.emit(&Emitter::death(e.color), e.position, None);
+
+		if e.bonus {
 			self.score += 1_000_000.0;
 		}
 
-		if thread_rng().gen_range(0..100) < 1 {
+		if self.rng.gen_range(0..100) < 1 {
 			self.pickups.push(Pickup {
 				position: e.position,
 			});
@@ -499,7 +907,11 @@ impl Game {
 		}
 
 		for upgrade in todo {
-			self.push_alert(Alert::with_color(format!("{}", upgrade.kind), Color::GREEN));
+			self.push_alert(Alert::with_color(
+				format!("{}", upgrade.kind),
+				self.theme.upgrade_alert,
+			));
+			self.audio.play(Sound::Upgrade);
 
 			macro_rules! cut_cooldown {
 				($index:literal) => {
@@ -519,6 +931,28 @@ impl Game {
 				};
 			}
 
+			macro_rules! imbue {
+				($index:literal, $effect:expr) => {
+					*self.player.weapons[$index].effect_mut() = Some($effect)
+				};
+			}
+
+			macro_rules! ricochet {
+				($index:literal, $bounces:literal) => {
+					if let Some(ricochet) = self.player.weapons[$index].ricochet_mut() {
+						*ricochet += $bounces
+					}
+				};
+			}
+
+			macro_rules! widen {
+				($index:literal) => {
+					if let Some(spread) = self.player.weapons[$index].spread_mut() {
+						spread.widen();
+					}
+				};
+			}
+
 			macro_rules! unlock {
 				($index:literal $pickup:path) => {{
 					self.player.weapons[$index].ammo_mut().reload();
@@ -538,32 +972,96 @@ impl Game {
 				UpgradeType::ShotgunDoubleAmmo => double_ammo!(2),
 				UpgradeType::BarrelDoubleAmmo => double_ammo!(3),
 				UpgradeType::WallUnlock => unlock!(4 AmmoPickup::Wall),
+				UpgradeType::ShotgunWide => widen!(2),
+				UpgradeType::PistolRicochet => ricochet!(0, 2),
+				UpgradeType::UziIncendiary => {
+					imbue!(1, StatusEffect::burn(3.0, Duration::from_secs(2)))
+				}
+				UpgradeType::ShotgunConcussion => {
+					imbue!(2, StatusEffect::stun(Duration::from_millis(500)))
+				}
+				UpgradeType::PistolMarking => {
+					imbue!(0, StatusEffect::vulnerable(0.5, Duration::from_secs(3)))
+				}
 			}
 		}
 	}
 
 	fn explode<E: Explosive>(&mut self, explosives: Vec<E>) {
 		for explosive in explosives {
-			for wall in self.walls.iter_mut() {
-				if explosive.details().colides_with(wall) {
-					explosive.explode_on(wall, false);
+			let details = explosive.details();
+			let id = self.stats.explosions;
+			self.stats.explosions += 1;
+			self.audio
+				.play_at(Sound::Explosion, details.position, self.player.position);
+			self.particles
+				.emit(&Emitter::explosion(&self.theme), details.position, None);
+			let distance = details.position.distance_with(self.player.position);
+			self.camera.add_trauma(0.6 / (1.0 + distance / 8.0));
+
+			for idx in 0..self.walls.len() {
+				if details.colides_with(&self.walls[idx]) {
+					let cover =
+						Self::blast_cover(&self.walls, details.position, self.walls[idx].position);
+					let before = self.walls[idx].health;
+					explosive.explode_on(&mut self.walls[idx], cover);
+
+					// Full cover or the edge of the blast leaves nothing to show
+					let dealt = before - self.walls[idx].health;
+					if dealt > 0.0 {
+						self.floating.push(FloatingText::new(
+							self.walls[idx].position,
+							dealt,
+							Style::Explosion,
+						));
+					}
 				}
 			}
 
 			for enemy in self.enemies.iter_mut() {
-				if explosive.details().colides_with(enemy) {
-					explosive.explode_on(enemy, true);
+				if details.colides_with(enemy) {
+					let cover = Self::blast_cover(&self.walls, details.position, enemy.position);
+					let before = enemy.health;
+					explosive.explode_on(enemy, cover);
+
+					let dealt = before - enemy.health;
+					if dealt > 0.0 {
+						self.floating.push(FloatingText::new(
+							enemy.position,
+							dealt,
+							Style::Explosion,
+						));
+					}
+					explosive.knock(enemy, cover);
+					enemy.last_hit = Some(KillSource::Explosion(id));
+
+					if let Some(effect) = details.effect {
+						enemy.effects.apply(effect);
+					}
 				}
 			}
 
+			// Barrels don't blow up right away so a chain reads as a chain
 			for barrel in self.barrels.iter_mut() {
-				if explosive.details().colides_with(barrel) {
-					explosive.explode_on(barrel, false);
+				if details.colides_with(barrel) {
+					let cover = Self::blast_cover(&self.walls, details.position, barrel.position);
+					if explosive.damage_on(barrel, cover) >= barrel.health {
+						barrel.light();
+					}
 				}
 			}
 
-			if explosive.details().colides_with(&self.player) {
-				explosive.explode_on(&mut self.player, true)
+			if details.colides_with(&self.player) {
+				let cover = Self::blast_cover(&self.walls, details.position, self.player.position);
+				let before = self.player.health;
+				explosive.explode_on(&mut self.player, cover);
+				self.stats
+					.took(DamageSource::Explosion, before - self.player.health);
+				explosive.knock(&mut self.player, cover);
+
+				if let Some(effect) = details.effect {
+					self.player.effects.apply(effect);
+				}
 			}
 			self.explosions.push(Explosion {
 				position: explosive.details().position,
@@ -571,9 +1069,50 @@ impl Game {
 				ending_radius: explosive.details().radius * MUR as f32,
 				cooldown: Cooldown::waiting(Duration::from_millis(100)),
 			});
+
+			if let Some(hazard) = explosive.leaves() {
+				self.hazards.push(hazard);
+			}
+		}
+	}
+
+	fn tick_hazards(&mut self, delta: Duration) {
+		self.hazards.retain_mut(|hazard| {
+			hazard.lifetime.subtract(delta);
+			!hazard.lifetime.is_ready()
+		});
+
+		for hazard in self.hazards.iter_mut() {
+			hazard.pulse.subtract(delta);
+			if !hazard.pulse.is_ready() {
+				continue;
+			}
+			hazard.pulse.reset();
+
+			for enemy in self.enemies.iter_mut() {
+				if hazard.colides_with(enemy) {
+					enemy.effects_mut().apply(hazard.effect);
+				}
+			}
+
+			if hazard.colides_with(&self.player) {
+				self.player.effects_mut().apply(hazard.effect);
+			}
 		}
 	}
 
+	/// How much of a blast at `from` reaches `to`. Every wall in between soaks
+	/// some of it up. A wall at `to` is what's being hit, so it doesn't count.
+	fn blast_cover(walls: &[thing::Wall], from: Vec2, to: Vec2) -> f32 {
+		walls
+			.iter()
+			.filter(|wall| wall.position != to)
+			.filter(|wall| {
+				util::segment_circle(from, to, wall.position, wall.bounds().radius / 2.0).is_some()
+			})
+			.fold(1.0, |cover, _| cover * thing::Wall::BLAST_PASSTHROUGH)
+	}
+
 	pub fn shoot(&mut self) {
 		if !self.player.weapon().can_fire() {
 			return;
@@ -581,16 +1120,32 @@ impl Game {
 		self.player.weapon_mut().cooldown_mut().reset();
 
 		if !self.player.weapon_is_object() {
-			for mut bull in self.player.weapon().bullets(self.player.facing) {
+			for mut bull in self
+				.player
+				.weapon()
+				.bullets(self.player.facing, &mut self.rng)
+			{
 				bull.position = self.player.position;
+				bull.bounces = self.player.weapon().ricochet();
+				bull.effect = self.player.weapon().effect();
+				bull.weapon = self.player.weapon().name();
+				self.stats.weapon(bull.weapon).shots += 1;
 
 				self.bullets.push(bull);
 			}
+
+			self.particles.emit(
+				&Emitter::muzzle_flash(&self.theme),
+				self.player.position + self.player.facing * 0.5,
+				Some(self.player.facing),
+			);
 		} else {
 			if !self.place_object() {
 				return;
 			}
 		}
+		self.audio.play(Sound::Gunshot(self.player.weapon().name()));
+		self.player.animation.play("player_shoot");
 
 		self.player.weapon_mut().ammo_mut().decrement();
 
@@ -627,6 +1182,7 @@ impl Game {
 		}
 
 		if self.player.selected_weapon == 4 {
+			self.stats.walls_placed += 1;
 			self.walls.push(thing::Wall {
 				position,
 				health: thing::Wall::WALL_HEALTH,
@@ -634,10 +1190,7 @@ impl Game {
 
 			true
 		} else if self.player.selected_weapon == 3 {
-			self.barrels.push(thing::Barrel {
-				position,
-				health: 1.0,
-			});
+			self.barrels.push(thing::Barrel::new(position));
 
 			true
 		} else {
@@ -657,37 +1210,40 @@ impl Game {
 			.is_some()
 	}
 
-	fn do_bullet_hits<H: Hittable + Colideable>(
-		hittables: &mut Vec<H>,
-		bullets: &mut Vec<Bullet>,
-		player_position: Option<Vec2>,
-	) {
-		'enemy: for enemy in hittables.iter_mut() {
-			let mut unhit_bullets = vec![];
-
-			loop {
-				match bullets.pop() {
-					None => {
-						bullets.extend(unhit_bullets);
-						break;
-					}
-					Some(bullet) => {
-						if enemy.was_hit(&bullet) {
-							enemy.hit(&bullet);
-							bullets.extend(unhit_bullets);
-
-							if let Some(pos) = player_position {
-								let dir = (enemy.bounds().position - pos).normalize_correct();
-								let pushback = dir * (enemy.bounds().radius / 2.0);
-								*enemy.position_mut() = enemy.bounds().position + pushback;
-							}
-
-							continue 'enemy;
-						} else {
-							unhit_bullets.push(bullet);
-						}
-					}
-				}
+	/// Hurt whatever the bullet hit. Enemies are shoved afterwards, so their
+	/// hits are handed back.
+	fn bullet_hit(&mut self, target: BulletTarget, bullet: Bullet) -> Option<(usize, Bullet)> {
+		match target {
+			BulletTarget::Enemy(idx) => {
+				let before = self.enemies[idx].health;
+				self.enemies[idx].hit(&bullet);
+				let dealt = before - self.enemies[idx].health;
+				let style = if dealt > bullet.damage {
+					Style::Crit
+				} else {
+					Style::Hit
+				};
+				self.floating
+					.push(FloatingText::new(bullet.position, dealt, style));
+
+				self.stats.weapon(bullet.weapon).hits += 1;
+				self.audio
+					.play_at(Sound::Hit, bullet.position, self.player.position);
+				self.particles.emit(
+					&Emitter::blood(self.enemies[idx].color),
+					bullet.position,
+					Some(bullet.velocity),
+				);
+				Some((idx, bullet))
+			}
+			BulletTarget::Barrel(idx) => {
+				self.barrels[idx].hit(&bullet);
+				self.particles.emit(
+					&Emitter::impact(&self.theme),
+					bullet.position,
+					Some(bullet.velocity * -1.0),
+				);
+				None
 			}
 		}
 	}
@@ -731,11 +1287,109 @@ impl Game {
 		];
 
 		for (pos, dim) in walls {
-			self.rect(pos, dim, Color::grey(0.5))
+			self.rect(pos, dim, self.theme.room_wall)
 		}
 	}
 
-	fn collide_walls<C: Colideable>(thing: &mut C) {
+	/// The edges of the room that bullets can't pass. Normals point inwards.
+	fn room_edges() -> [Edge; 4] {
+		let hrw = Game::ROOM_WIDTH / 2.0;
+		let hrh = Game::ROOM_HEIGHT / 2.0;
+
+		[
+			Edge::new((-hrw, hrh), (hrw, hrh), (0.0, -1.0)),
+			Edge::new((hrw, hrh), (hrw, -hrh), (-1.0, 0.0)),
+			Edge::new((hrw, -hrh), (-hrw, -hrh), (0.0, 1.0)),
+			Edge::new((-hrw, -hrh), (-hrw, hrh), (1.0, 0.0)),
+		]
+	}
+
+	/// Move bullets along their path, stopping or bouncing them where they
+	/// cross an edge of the room or run into a placed wall. Every leg of the
+	/// path is checked for enemies and barrels before we bounce, and the first
+	/// one along it is what gets hit. Walls take damage from every bullet that
+	/// reaches them, bounce or not.
+	fn move_bullets(&mut self, dsec: f32) -> Vec<(usize, Bullet)> {
+		let mut enemy_hits = vec![];
+		let mut moving = vec![];
+
+		'bullets: for mut bul in std::mem::take(&mut self.bullets) {
+			let mut travel = bul.velocity * dsec;
+
+			loop {
+				let start = bul.position;
+				let end = start + travel;
+				let solid = self.solid_hit(start, end);
+				let leg_end = start + travel * solid.map_or(1.0, |(t, ..)| t);
+
+				let target = [
+					traits::first_hit(&self.enemies, start, leg_end)
+						.map(|(i, t)| (t, BulletTarget::Enemy(i))),
+					traits::first_hit(&self.barrels, start, leg_end)
+						.map(|(i, t)| (t, BulletTarget::Barrel(i))),
+				]
+				.into_iter()
+				.flatten()
+				.min_by(|a, b| a.0.total_cmp(&b.0));
+
+				if let Some((t, target)) = target {
+					bul.position = start + (leg_end - start) * t;
+					enemy_hits.extend(self.bullet_hit(target, bul));
+					continue 'bullets;
+				}
+
+				let (t, normal, wall) = match solid {
+					Some(solid) => solid,
+					None => {
+						bul.position = end;
+						moving.push(bul);
+						continue 'bullets;
+					}
+				};
+
+				let point = start + travel * t;
+				self.particles
+					.emit(&Emitter::impact(&self.theme), point, Some(normal));
+
+				if let Some(idx) = wall {
+					self.walls[idx].hit(&bul);
+					self.floating
+						.push(FloatingText::new(point, bul.damage, Style::Hit));
+				}
+
+				if bul.bounces == 0 {
+					continue 'bullets;
+				}
+				bul.bounces -= 1;
+
+				// Nudge off the edge so we don't hit it again straight away
+				bul.position = point + normal * 0.001;
+				bul.velocity = util::reflect(bul.velocity, normal);
+				travel = util::reflect(travel * (1.0 - t), normal);
+			}
+		}
+
+		self.bullets = moving;
+		enemy_hits
+	}
+
+	/// The first edge of the room or placed wall on the way from `start` to
+	/// `end`. How far along the way it is, the normal to bounce off of, and
+	/// which wall it was if it was one.
+	fn solid_hit(&self, start: Vec2, end: Vec2) -> Option<(f32, Vec2, Option<usize>)> {
+		let edges = Self::room_edges().into_iter().filter_map(|edge| {
+			util::segment_segment(start, end, edge.start, edge.end).map(|t| (t, edge.normal, None))
+		});
+		let walls = self.walls.iter().enumerate().filter_map(|(idx, wall)| {
+			let t = wall.hit_at(start, end)?;
+			let point = start + (end - start) * t;
+			Some((t, (point - wall.position).normalize_correct(), Some(idx)))
+		});
+
+		edges.chain(walls).min_by(|a, b| a.0.total_cmp(&b.0))
+	}
+
+	fn collide_walls<P: Physical>(thing: &mut P) {
 		let bounds = thing.bounds();
 		let p = bounds.position;
 		let r = bounds.radius;
@@ -749,48 +1403,46 @@ impl Game {
 		let hrh = Game::ROOM_HEIGHT / 2.0;
 
 		let mpos = thing.position_mut();
+		let mut normals = vec![];
 		if top > hrh {
 			mpos.y = hrh - r;
+			normals.push(Vec2::new(0.0, -1.0));
 		} else if btm < -hrh {
 			mpos.y = -hrh + r;
+			normals.push(Vec2::new(0.0, 1.0));
 		}
 
 		if lft < -hrw {
 			mpos.x = -hrw + r;
+			normals.push(Vec2::new(1.0, 0.0));
 		} else if rht > hrw {
 			mpos.x = hrw - r;
+			normals.push(Vec2::new(-1.0, 0.0));
+		}
+
+		for normal in normals {
+			thing.body_mut().stop_against(normal);
 		}
 	}
 
 	fn draw_grid(&self) {
-		let mur_width = (DIM.0 / MUR) + 4;
-		let mur_height = (DIM.1 / MUR) + 4;
-
-		for x in 0..mur_width {
-			for y in 0..mur_height {
-				let x = x as f32 - mur_width as f32 / 2.0;
-				let y = y as f32 - mur_height as f32 / 2.0;
-
-				let pos_incorrect =
-					Vec2::new(x.floor(), y.floor()) - self.player.position.operation(f32::trunc);
-
-				let light = Color::rgb(0.88, 0.88, 0.78);
-				let dark = Color::rgb(0.68, 0.68, 0.58);
-
-				let color = match (
-					pos_incorrect.x.abs().floor() as u32 % 2 == 0,
-					pos_incorrect.y.abs().floor() as u32 % 2 == 0,
-				) {
-					(true, true) => light,
-					(false, true) => dark,
-					(true, false) => dark,
-					(false, false) => light,
+		let view = self.camera.view();
+		let low = (self.camera.position - view).operation(f32::floor);
+		let high = (self.camera.position + view).operation(f32::ceil);
+
+		let light = self.theme.grid_light;
+		let dark = self.theme.grid_dark;
+
+		// One past the view on every side so shake doesn't show the edge
+		for x in (low.x as i32 - 1)..=(high.x as i32 + 1) {
+			for y in (low.y as i32 - 1)..=(high.y as i32 + 1) {
+				let color = if (x + y).rem_euclid(2) == 0 {
+					light
+				} else {
+					dark
 				};
 
-				let camera = self.player.position.operation(f32::fract);
-
-				let pos = Vec2::new(x.floor(), y.floor()) - camera;
-				self.smitten.rect(pos, Vec2::new(1.0, 1.0), color)
+				self.rect((x as f32, y as f32), (1.0, 1.0), color)
 			}
 		}
 	}
@@ -799,27 +1451,33 @@ impl Game {
 		for enemy in self.enemies.iter_mut() {
 			enemy.cooldown.subtract(delta);
 
-			if colide_and_move(&self.player, enemy) {
+			let burn = enemy.effects.tick(delta);
+			enemy.damage(burn);
+			if burn > 0.0 && enemy.health <= 0.0 {
+				enemy.last_hit = Some(KillSource::Burn);
+			}
+			let can_attack = !enemy.effects.is_stunned();
+
+			if physics::separate(&mut self.player, enemy) {
 				enemy.should_move_next_frame = false;
-				if enemy.cooldown.is_ready() {
+				if can_attack && enemy.cooldown.is_ready() {
 					enemy.cooldown.reset();
-					self.player.health -= 6.66;
+					enemy.animation.play("enemy_attack");
+					let before = self.player.health;
+					self.player.damage(6.66);
+					self.stats
+						.took(DamageSource::Enemy, before - self.player.health);
 				}
 			}
 
 			for wall in self.walls.iter_mut() {
 				enemy.should_move_next_frame = false;
-				if colide_and_move(wall, enemy) {
-					if enemy.cooldown.is_ready() {
-						enemy.cooldown.reset();
-						wall.health -= 6.66;
-					}
+				if wall.colides_with(enemy) && can_attack && enemy.cooldown.is_ready() {
+					enemy.cooldown.reset();
+					enemy.animation.play("enemy_attack");
+					wall.health -= 6.66;
 				}
 			}
-
-			for barrel in self.barrels.iter() {
-				colide_and_move(barrel, enemy);
-			}
 		}
 
 		//Movement
@@ -828,23 +1486,7 @@ impl Game {
 		let fix = |enemy: &mut Enemy, others: &mut [Enemy]| -> bool {
 			let mut moved = false;
 			others.iter_mut().for_each(|other| {
-				let dist = enemy.position.distance_with(other.position);
-
-				if dist < enemy.bounds().radius {
-					let dir = enemy.position - other.position;
-					//desired sepration
-					let wanted = dir.normalize_correct() * (enemy.bounds().radius - dir.length());
-
-					let _collective_speed = enemy.speed + other.speed;
-
-					enemy.position += wanted; //* (collective_speed - (enemy.speed / collective_speed));
-						  /*other.position -=
-						  wanted * (collective_speed - (other.speed / collective_speed));*/
-
-					//enemy.should_move_next_frame = false;
-					//other.should_move_next_frame = false;
-					moved = true;
-				}
+				moved |= physics::separate(enemy, other);
 			});
 			moved
 		};
@@ -853,15 +1495,24 @@ impl Game {
 			match self.enemies.pop() {
 				None => break,
 				Some(mut enemy) => {
-					Self::collide_walls(&mut enemy);
+					enemy.integrate(delta);
 
 					let direction = (self.player.position - enemy.position).normalize_correct();
-					let movement = direction * enemy.speed;
+					let movement = direction * enemy.speed * enemy.effects.speed_scale();
 					enemy.position += movement * delta.as_secs_f32();
 
 					fix(&mut enemy, &mut self.enemies);
 					fix(&mut enemy, &mut moved);
 
+					// Static things last so nothing gets shoved through them
+					Self::collide_walls(&mut enemy);
+					for wall in self.walls.iter() {
+						physics::collide_static(wall, &mut enemy);
+					}
+					for barrel in self.barrels.iter() {
+						physics::collide_static(barrel, &mut enemy);
+					}
+
 					moved.push(enemy);
 				}
 			}
@@ -910,30 +1561,37 @@ impl Game {
 					(false, true, false) => 3,
 				},
 			);
+			self.overlay.spawn_corners = corners.clone();
 
+			let rng = &mut self.rng;
 			let randoms: Vec<Enemy> = std::iter::from_fn(move || {
-				let corner = corners[thread_rng().gen_range(0..corners.len())];
-				Some(
-					corner
-						+ Vec2::new(
-							thread_rng().gen_range(0.0..Game::WAVE_SPAWN_AREA),
-							thread_rng().gen_range(0.0..Game::WAVE_SPAWN_AREA),
-						),
-				)
+				let corner = corners[rng.gen_range(0..corners.len())];
+				let position = corner
+					+ Vec2::new(
+						rng.gen_range(0.0..Game::WAVE_SPAWN_AREA),
+						rng.gen_range(0.0..Game::WAVE_SPAWN_AREA),
+					);
+				Some((position, rng.gen_range(Enemy::MASS)))
 			})
 			.take(3 + self.score_multiplier.current as usize)
-			.map(|position| Enemy {
+			.map(|(position, mass)| Enemy {
 				position,
-				color: Color::YELLOW,
+				body: Body::new(mass),
+				color: self.theme.enemy,
 				health: 25.0,
+				max_health: 25.0,
 				speed: 0.75,
 				cooldown: Cooldown::ready(Duration::from_secs(2)),
 				should_move_next_frame: true,
+				effects: Effects::default(),
+				last_hit: None,
+				animation: Animator::new("enemy_walk"),
+				bonus: false,
 			})
 			.collect();
 
 			self.enemies.extend(randoms);
-			self.wave_count += 1;
+			self.stats.waves += 1;
 		}
 	}
 
@@ -972,10 +1630,12 @@ impl Game {
 		for pickup in unchecked {
 			if pickup.colides_with(&self.player) {
 				if self.possible_pickups.len() > 0 {
-					let r: usize = thread_rng().gen_range(0..self.possible_pickups.len());
+					let r: usize = self.rng.gen_range(0..self.possible_pickups.len());
 					let pickup = self.possible_pickups[r];
 					self.player.pickedup(pickup);
-					self.push_alert(Alert::new(format!("{}", pickup)));
+					self.stats.pickups += 1;
+					self.audio.play(Sound::Pickup);
+					self.push_alert(Alert::with_color(format!("{}", pickup), self.theme.alert));
 				}
 			} else {
 				checked.push(pickup);
@@ -1001,7 +1661,7 @@ impl Game {
 			self.pickup_respawn.reset();
 
 			let positions = Self::pickup_locations();
-			let r = thread_rng().gen_range(0..positions.len());
+			let r = self.rng.gen_range(0..positions.len());
 			let position = positions[r];
 
 			for pik in &self.pickups {
@@ -1024,6 +1684,14 @@ struct Player {
 	weapons: Vec<Box<dyn Weapon>>,
 	must_release_shoot: bool,
 	selected_weapon: usize,
+	body: Body,
+	effects: Effects,
+	invulnerable: Cooldown,
+	/// Whether something got through invulnerability since the last tick
+	hurt: bool,
+	/// Whether we moved this frame
+	moving: bool,
+	animation: Animator,
 }
 
 impl Player {
@@ -1035,8 +1703,31 @@ impl Player {
 		&mut self.weapons[self.selected_weapon]
 	}
 
-	pub fn tick(&mut self, delta: Duration) {
+	const MASS: f32 = 2.0;
+	/// How long we can't be hurt for after getting hit
+	const INVULNERABILITY: Duration = Duration::from_millis(750);
+	const HEALTH_PACK: f32 = 10.0;
+
+	/// Returns how much burn damage we took
+	pub fn tick(&mut self, delta: Duration) -> f32 {
 		self.weapon_mut().cooldown_mut().subtract(delta);
+		self.invulnerable.subtract(delta);
+
+		// Burning ticks every frame, so it ignores invulnerability rather
+		// than setting it off
+		let burn = self.effects.tick(delta) * self.vulnerability();
+		self.health -= burn;
+		burn
+	}
+
+	pub fn heal(&mut self, amount: f32) {
+		self.health = (self.health + amount).min(Game::PLAYER_HEALTH_MAX);
+	}
+
+	/// Blink while we're invulnerable so it's obvious
+	pub fn is_flashing(&self) -> bool {
+		!self.invulnerable.is_ready()
+			&& ((self.invulnerable.percent() * 10.0) as u32).is_multiple_of(2)
 	}
 
 	/// Returns a bool indicating if the indexed weapon could be selected
@@ -1088,6 +1779,10 @@ impl Player {
 			AmmoPickup::Shotgun => 2,
 			AmmoPickup::Wall => 4,
 			AmmoPickup::Barrel => 3,
+			AmmoPickup::Health => {
+				self.heal(Player::HEALTH_PACK);
+				return;
+			}
 		};
 
 		self.weapons[weapon_index].ammo_mut().reload();
@@ -1111,6 +1806,16 @@ impl Colideable for Player {
 	}
 }
 
+impl Physical for Player {
+	fn body(&self) -> &Body {
+		&self.body
+	}
+
+	fn body_mut(&mut self) -> &mut Body {
+		&mut self.body
+	}
+}
+
 impl Destructible for Player {
 	fn health(&self) -> f32 {
 		self.health
@@ -1119,6 +1824,30 @@ impl Destructible for Player {
 	fn health_mut(&mut self) -> &mut f32 {
 		&mut self.health
 	}
+
+	fn vulnerability(&self) -> f32 {
+		self.effects.damage_scale()
+	}
+
+	fn damage(&mut self, amount: f32) {
+		if amount <= 0.0 || !self.invulnerable.is_ready() {
+			return;
+		}
+
+		self.health -= amount * self.vulnerability();
+		self.invulnerable.reset();
+		self.hurt = true;
+	}
+}
+
+impl Affected for Player {
+	fn effects(&self) -> &Effects {
+		&self.effects
+	}
+
+	fn effects_mut(&mut self) -> &mut Effects {
+		&mut self.effects
+	}
 }
 
 impl Default for Player {
@@ -1136,6 +1865,12 @@ impl Default for Player {
 			],
 			must_release_shoot: false,
 			selected_weapon: 0,
+			body: Body::new(Player::MASS),
+			effects: Effects::default(),
+			invulnerable: Cooldown::ready(Player::INVULNERABILITY),
+			hurt: false,
+			moving: false,
+			animation: Animator::new("player_idle"),
 		}
 	}
 }
@@ -1163,30 +1898,13 @@ fn color_lerp(a: Color, b: Color, c: f32) -> Color {
 	Color { r, g, b: bl, a }
 }
 
-fn colide_and_move<A: Colideable, B: Colideable>(a: &A, b: &mut B) -> bool {
-	let abound = a.bounds();
-	let bbound = b.bounds();
-
-	let dist = abound.position.distance_with(bbound.position);
-
-	if dist < abound.radius {
-		let dir = abound.position - bbound.position;
-		//desired sepration
-		let wanted = dir.normalize_correct() * (abound.radius - dir.length());
-
-		*b.position_mut() -= wanted;
-		true
-	} else {
-		false
-	}
-}
-
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum AmmoPickup {
 	Uzi,
 	Shotgun,
 	Wall,
 	Barrel,
+	Health,
 }
 
 #[derive(Clone, Debug)]
@@ -1197,10 +1915,6 @@ struct Alert {
 }
 
 impl Alert {
-	pub fn new(message: String) -> Self {
-		Self::with_color(message, Color::WHITE)
-	}
-
 	pub fn with_color(message: String, color: Color) -> Alert {
 		Self {
 			message,
@@ -1228,6 +1942,7 @@ impl std::fmt::Display for AmmoPickup {
 			AmmoPickup::Shotgun => "shotgun ammo",
 			AmmoPickup::Wall => "wall ammo",
 			AmmoPickup::Barrel => "barrel ammo",
+			AmmoPickup::Health => "health pack",
 		};
 
 		write!(f, "{}", stat)
@@ -1275,7 +1990,12 @@ impl Upgrade {
 			// Grenade unlock
 			125000 UpgradeType::ShotgunDoubleAmmo,
 			175000 UpgradeType::BarrelDoubleAmmo,
-			250000 UpgradeType::WallUnlock
+			250000 UpgradeType::WallUnlock,
+			300000 UpgradeType::ShotgunWide,
+			400000 UpgradeType::PistolRicochet,
+			450000 UpgradeType::UziIncendiary,
+			500000 UpgradeType::ShotgunConcussion,
+			550000 UpgradeType::PistolMarking
 		);
 
 		ret
@@ -1296,7 +2016,11 @@ enum UpgradeType {
 	// Uzi long shot?
 	BarrelDoubleAmmo,
 	WallUnlock,
-	// Shotgun wide shot?
+	ShotgunWide,
+	PistolRicochet,
+	UziIncendiary,
+	ShotgunConcussion,
+	PistolMarking,
 	// Barrel big bang
 }
 
@@ -1314,6 +2038,11 @@ impl std::fmt::Display for UpgradeType {
 			UpgradeType::ShotgunDoubleAmmo => "shotgun double ammo",
 			UpgradeType::BarrelDoubleAmmo => "barrel double ammo",
 			UpgradeType::WallUnlock => "wall unlock",
+			UpgradeType::ShotgunWide => "shotgun wide shot",
+			UpgradeType::PistolRicochet => "pistol ricochet",
+			UpgradeType::UziIncendiary => "uzi incendiary rounds",
+			UpgradeType::ShotgunConcussion => "shotgun concussive shells",
+			UpgradeType::PistolMarking => "pistol marking rounds",
 		};
 
 		write!(f, "{}", stat)
@@ -1381,3 +2110,26 @@ struct Explosion {
 	ending_radius: f32,
 	cooldown: Cooldown,
 }
+
+enum BulletTarget {
+	Enemy(usize),
+	Barrel(usize),
+}
+
+/// A line segment bullets stop at. The room boundary is made of these and so
+/// can anything else we put in the map later.
+struct Edge {
+	start: Vec2,
+	end: Vec2,
+	normal: Vec2,
+}
+
+impl Edge {
+	pub fn new<S: Into<Vec2>, E: Into<Vec2>, N: Into<Vec2>>(start: S, end: E, normal: N) -> Self {
+		Self {
+			start: start.into(),
+			end: end.into(),
+			normal: normal.into(),
+		}
+	}
+}
@@ -0,0 +1,251 @@
+use smitten::{Key, SmittenEvent};
+
+use crate::{
+	audio::Category,
+	screen::Scaling,
+	settings::{Action, Settings},
+	state::{banner, write},
+	theme::ThemeChoice,
+	Game,
+};
+
+/// What the pause menu wants the game to do
+pub enum Choice {
+	Resume,
+	Restart,
+	QuitToTitle,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Page {
+	Main,
+	Settings,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Item {
+	Resume,
+	Restart,
+	Settings,
+	QuitToTitle,
+	Volume,
+	CategoryVolume(Category),
+	Binding(Action),
+	ShowMessages,
+	ShowDamageNumbers,
+	ShowHealthBars,
+	Regeneration,
+	Fullscreen,
+	Scaling,
+	Theme,
+	Developer,
+	DebugOverlay,
+	Back,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PauseMenu {
+	page: Page,
+	selected: usize,
+	/// Waiting for a key to bind to this
+	rebinding: Option<Action>,
+	/// The last key we tried to bind that the game needs for something else
+	refused: Option<Key>,
+}
+
+impl PauseMenu {
+	fn items(&self) -> Vec<Item> {
+		match self.page {
+			Page::Main => vec![
+				Item::Resume,
+				Item::Restart,
+				Item::Settings,
+				Item::QuitToTitle,
+			],
+			Page::Settings => {
+				let mut items = vec![Item::Volume];
+				items.extend(Category::ALL.iter().map(|cat| Item::CategoryVolume(*cat)));
+				items.extend(Action::ALL.iter().map(|action| Item::Binding(*action)));
+				items.extend([
+					Item::ShowMessages,
+					Item::ShowDamageNumbers,
+					Item::ShowHealthBars,
+					Item::Regeneration,
+					Item::Fullscreen,
+					Item::Scaling,
+					Item::Theme,
+					Item::Developer,
+					Item::DebugOverlay,
+					Item::Back,
+				]);
+				items
+			}
+		}
+	}
+
+	fn open(&mut self, page: Page) {
+		self.page = page;
+		self.selected = 0;
+	}
+
+	pub fn input(&mut self, settings: &mut Settings, events: &[SmittenEvent]) -> Option<Choice> {
+		let released = events.iter().filter_map(|e| match e {
+			SmittenEvent::Keyup { key, .. } => *key,
+			_ => None,
+		});
+
+		for key in released {
+			if let Some(action) = self.rebinding.take() {
+				self.refused = None;
+				if key != Key::Escape && !settings.bindings.rebind(action, key) {
+					// Let them try another
+					self.rebinding = Some(action);
+					self.refused = Some(key);
+				}
+				continue;
+			}
+
+			let items = self.items();
+			let bindings = settings.bindings;
+
+			if key == bindings.up {
+				self.selected = (self.selected + items.len() - 1) % items.len();
+			} else if key == bindings.down {
+				self.selected = (self.selected + 1) % items.len();
+			} else if key == bindings.left || key == bindings.right {
+				if let Some(volume) = Self::volume_mut(items[self.selected], settings) {
+					let step = if key == bindings.left { -0.1 } else { 0.1 };
+					*volume = (*volume + step).clamp(0.0, 1.0);
+				}
+			} else if key == Key::Escape || key == Key::P {
+				match self.page {
+					Page::Main => return Some(Choice::Resume),
+					Page::Settings => self.open(Page::Main),
+				}
+			} else if key == Key::Space {
+				match items[self.selected] {
+					Item::Resume => return Some(Choice::Resume),
+					Item::Restart => return Some(Choice::Restart),
+					Item::QuitToTitle => return Some(Choice::QuitToTitle),
+					Item::Settings => self.open(Page::Settings),
+					Item::Back => self.open(Page::Main),
+					item @ (Item::Volume | Item::CategoryVolume(_)) => {
+						// Wrap around so you can get anywhere without left and right
+						let volume = Self::volume_mut(item, settings).unwrap();
+						*volume = if *volume >= 1.0 {
+							0.0
+						} else {
+							(*volume + 0.1).min(1.0)
+						};
+					}
+					Item::Binding(action) => self.rebinding = Some(action),
+					Item::ShowMessages => settings.show_messages = !settings.show_messages,
+					Item::ShowDamageNumbers => {
+						settings.show_damage_numbers = !settings.show_damage_numbers
+					}
+					Item::ShowHealthBars => settings.show_health_bars = !settings.show_health_bars,
+					Item::Regeneration => settings.regeneration = !settings.regeneration,
+					Item::Fullscreen => settings.fullscreen = !settings.fullscreen,
+					Item::Scaling => {
+						let choices = Scaling::CHOICES;
+						let current = choices.iter().position(|s| *s == settings.scaling);
+						let next = current.map(|idx| idx + 1).unwrap_or(0) % choices.len();
+						settings.scaling = choices[next];
+					}
+					Item::Theme => {
+						let choices = ThemeChoice::CHOICES;
+						let current = choices.iter().position(|t| *t == settings.theme);
+						let next = current.map(|idx| idx + 1).unwrap_or(0) % choices.len();
+						settings.theme = choices[next];
+					}
+					Item::Developer => settings.developer = !settings.developer,
+					Item::DebugOverlay => settings.debug_overlay = !settings.debug_overlay,
+				}
+			}
+		}
+
+		None
+	}
+
+	fn volume_mut(item: Item, settings: &mut Settings) -> Option<&mut f32> {
+		match item {
+			Item::Volume => Some(&mut settings.volume),
+			Item::CategoryVolume(category) => Some(settings.volumes.get_mut(category)),
+			_ => None,
+		}
+	}
+
+	fn label(&self, item: Item, settings: &Settings) -> String {
+		let on_off = |b: bool| if b { "on" } else { "off" };
+
+		match item {
+			Item::Resume => String::from("resume"),
+			Item::Restart => String::from("restart"),
+			Item::Settings => String::from("settings"),
+			Item::QuitToTitle => String::from("quit to title"),
+			Item::Volume => format!("volume < {:.0}% >", settings.volume * 100.0),
+			Item::CategoryVolume(category) => format!(
+				"{} volume < {:.0}% >",
+				category,
+				settings.volumes.get(category) * 100.0
+			),
+			Item::Binding(action) if self.rebinding == Some(action) => match self.refused {
+				Some(key) => format!("{}: {:?} is taken, press another", action.name(), key),
+				None => format!("{}: press a key", action.name()),
+			},
+			Item::Binding(action) => {
+				format!("{}: {:?}", action.name(), settings.bindings.key(action))
+			}
+			Item::ShowMessages => format!("show messages: {}", on_off(settings.show_messages)),
+			Item::ShowDamageNumbers => {
+				format!("damage numbers: {}", on_off(settings.show_damage_numbers))
+			}
+			Item::ShowHealthBars => format!("health bars: {}", on_off(settings.show_health_bars)),
+			Item::Regeneration => format!("regeneration: {}", on_off(settings.regeneration)),
+			Item::Fullscreen => format!("fullscreen: {}", on_off(settings.fullscreen)),
+			Item::Scaling => format!("scaling: {}", settings.scaling.name()),
+			Item::Theme => format!("colors: {}", settings.theme.name()),
+			Item::Developer => format!("developer mode: {}", on_off(settings.developer)),
+			Item::DebugOverlay => format!("debug overlay: {}", on_off(settings.debug_overlay)),
+			Item::Back => String::from("back"),
+		}
+	}
+
+	pub fn draw(&self, game: &Game) {
+		let items = self.items();
+		let line = 0.6;
+		let top = (items.len() as f32 * line) / 2.0;
+
+		banner(game, items.len() as f32 * line + 2.0);
+
+		let title = match self.page {
+			Page::Main => "Paused",
+			Page::Settings => "Settings",
+		};
+		write(game, title, top + 0.5, game.theme.menu_text, 1.0);
+
+		for (idx, item) in items.into_iter().enumerate() {
+			let (text, color) = if idx == self.selected {
+				(
+					format!("> {} <", self.label(item, &game.settings)),
+					game.theme.highlight,
+				)
+			} else {
+				(self.label(item, &game.settings), game.theme.menu_text)
+			};
+
+			write(game, &text, top - 0.25 - idx as f32 * line, color, 0.5);
+		}
+	}
+}
+
+impl Default for PauseMenu {
+	fn default() -> Self {
+		Self {
+			page: Page::Main,
+			selected: 0,
+			rebinding: None,
+			refused: None,
+		}
+	}
+}
@@ -0,0 +1,104 @@
+use smitten::{Color, HorizontalAnchor, SignedDistance, Vec2, VerticalAnchor};
+
+use crate::Game;
+
+/// Width and height of the minimap, in MUR
+const SIZE: f32 = 4.0;
+/// Space between the minimap and the corner of the screen
+const MARGIN: f32 = 0.25;
+/// How far in from the edge of the screen the arrow tips sit
+const ARROW_INSET: f32 = 0.5;
+
+/// The whole room shrunk into the bottom left corner
+pub fn draw(game: &Game) {
+	let rect = |position: Vec2, dim: Vec2, color: Color| {
+		game.render.anchored_rect(
+			(
+				HorizontalAnchor::Left(MARGIN + position.x - dim.x / 2.0),
+				VerticalAnchor::Bottom(MARGIN + position.y - dim.y / 2.0),
+			),
+			dim,
+			color,
+		)
+	};
+
+	// Room and outline
+	let half = SIZE / 2.0;
+	let line = 0.05;
+	rect(
+		Vec2::new(half, half),
+		Vec2::new(SIZE, SIZE),
+		game.theme.panel,
+	);
+	for (position, dim) in [
+		((half, 0.0), (SIZE, line)),
+		((half, SIZE), (SIZE, line)),
+		((0.0, half), (line, SIZE)),
+		((SIZE, half), (line, SIZE)),
+	] {
+		rect(position.into(), dim.into(), game.theme.text);
+	}
+
+	let dot =
+		|world: Vec2, size: f32, color: Color| rect(to_map(world), Vec2::new(size, size), color);
+
+	for wall in &game.walls {
+		dot(wall.position, 0.1, game.theme.minimap_wall);
+	}
+
+	for barrel in &game.barrels {
+		dot(barrel.position, 0.1, game.theme.barrel);
+	}
+
+	for pickup in &game.pickups {
+		dot(pickup.position, 0.1, game.theme.pickup);
+	}
+
+	for enemy in &game.enemies {
+		dot(enemy.position, 0.12, enemy.color);
+	}
+
+	dot(game.player.position, 0.15, game.theme.player);
+}
+
+/// Where a point in the room is on the minimap, from its bottom left corner
+fn to_map(world: Vec2) -> Vec2 {
+	let x = (world.x / Game::ROOM_WIDTH + 0.5).clamp(0.0, 1.0);
+	let y = (world.y / Game::ROOM_HEIGHT + 0.5).clamp(0.0, 1.0);
+
+	Vec2::new(x * SIZE, y * SIZE)
+}
+
+/// An arrow at the edge of the screen for every enemy that's off of it. Closer
+/// enemies get bigger, more solid arrows.
+pub fn draw_indicators(game: &Game) {
+	let half_screen = game.screen.murs() / 2.0;
+	let edge = half_screen - Vec2::new(ARROW_INSET, ARROW_INSET);
+
+	for enemy in &game.enemies {
+		let screen = game.camera.to_screen(enemy.position);
+		if screen.x.abs() <= half_screen.x && screen.y.abs() <= half_screen.y {
+			continue;
+		}
+
+		// Slide back toward the middle until we're just inside the edge
+		let fit = (edge.x / screen.x.abs()).min(edge.y / screen.y.abs());
+		let tip = screen * fit;
+
+		let distance = enemy.position.distance_with(game.player.position);
+		let closeness = (1.0 - distance / Game::ROOM_WIDTH).clamp(0.0, 1.0);
+		let length = 0.2 + 0.3 * closeness;
+		let mut color = enemy.color;
+		color.a = 0.35 + 0.65 * closeness;
+
+		let angle = screen.angle();
+		for side in [150.0, -150.0] {
+			game.render.sdf(SignedDistance::LineSegment {
+				start: tip,
+				end: tip + Vec2::from_degrees(angle + side) * length,
+				thickness: 3,
+				color,
+			});
+		}
+	}
+}
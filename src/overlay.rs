@@ -0,0 +1,189 @@
+use std::{
+	collections::VecDeque,
+	time::{Duration, Instant},
+};
+
+use smitten::{Color, HorizontalAnchor, SignedDistance, Vec2, VerticalAnchor};
+
+use crate::{
+	traits::{Colideable, Hittable},
+	Game, MUR,
+};
+
+/// How many seconds of travel a bullet's velocity line shows
+const VELOCITY_SCALE: f32 = 0.05;
+/// Space between lines of text, in MUR
+const LINE: f32 = 0.4;
+
+/// What the debug overlay shows that the game doesn't otherwise keep around
+#[derive(Clone, Debug)]
+pub struct Overlay {
+	/// Bottom left corners of the squares the last wave could spawn in
+	pub spawn_corners: Vec<Vec2>,
+	/// Everywhere a pickup can show up
+	pub pickup_locations: Vec<Vec2>,
+	/// How long the last tick took to run
+	pub tick_time: Duration,
+	/// When each frame in the last second finished
+	frames: VecDeque<Instant>,
+}
+
+impl Overlay {
+	pub fn new(pickup_locations: Vec<Vec2>) -> Self {
+		Self {
+			spawn_corners: vec![],
+			pickup_locations,
+			tick_time: Duration::ZERO,
+			frames: VecDeque::new(),
+		}
+	}
+
+	/// Call once a frame, for the frame rate
+	pub fn frame(&mut self) {
+		let now = Instant::now();
+		self.frames.push_back(now);
+
+		while let Some(first) = self.frames.front() {
+			if now.duration_since(*first) > Duration::from_secs(1) {
+				self.frames.pop_front();
+			} else {
+				break;
+			}
+		}
+	}
+
+	pub fn fps(&self) -> usize {
+		self.frames.len()
+	}
+}
+
+/// Everything the game knows about where things are and where they're going
+pub fn draw(game: &Game) {
+	let theme = &game.theme;
+
+	for corner in &game.overlay.spawn_corners {
+		let area = Vec2::new(Game::WAVE_SPAWN_AREA, Game::WAVE_SPAWN_AREA);
+		game.rect(*corner + area / 2.0, area, theme.debug_spawn);
+	}
+
+	for location in &game.overlay.pickup_locations {
+		circle(game, *location, 0.15, theme.debug_pickup);
+	}
+
+	bounds(game, &game.player);
+	for pickup in &game.pickups {
+		bounds(game, pickup);
+	}
+	for hazard in &game.hazards {
+		bounds(game, hazard);
+	}
+	for wall in &game.walls {
+		bounds(game, wall);
+		hit_bounds(game, wall);
+	}
+	for barrel in &game.barrels {
+		bounds(game, barrel);
+		hit_bounds(game, barrel);
+	}
+
+	for enemy in &game.enemies {
+		bounds(game, enemy);
+		hit_bounds(game, enemy);
+
+		// Enemies walk straight at us. The path is where they'll be in a
+		// second if nothing's in the way.
+		let direction = (game.player.position - enemy.position).normalize_correct();
+		let speed = enemy.speed * enemy.effects.speed_scale();
+		line(
+			game,
+			enemy.position,
+			game.player.position,
+			theme.debug_target,
+		);
+		line(
+			game,
+			enemy.position,
+			enemy.position + direction * speed,
+			theme.debug_path,
+		);
+	}
+
+	for bullet in &game.bullets {
+		line(
+			game,
+			bullet.position,
+			bullet.position + bullet.velocity * VELOCITY_SCALE,
+			theme.debug_velocity,
+		);
+	}
+
+	let text = [
+		format!("fps {}", game.overlay.fps()),
+		format!(
+			"tick {:.2}ms",
+			game.overlay.tick_time.as_secs_f64() * 1000.0
+		),
+		format!("enemies {}", game.enemies.len()),
+		format!("bullets {}", game.bullets.len()),
+		format!("walls {}", game.walls.len()),
+		format!("barrels {}", game.barrels.len()),
+		format!("pickups {}", game.pickups.len()),
+		format!("hazards {}", game.hazards.len()),
+		format!("particles {}", game.particles.alive().len()),
+		format!("voices {}", game.audio.mixer.voices()),
+		format!("seed {}", game.seed),
+	];
+
+	// Under the wave timer
+	for (idx, line) in text.iter().enumerate() {
+		game.render.write(
+			line,
+			(
+				HorizontalAnchor::Left(0.25),
+				VerticalAnchor::Top(-1.25 - idx as f32 * LINE),
+			),
+			theme.debug_text,
+			0.35,
+		);
+	}
+}
+
+fn circle(game: &Game, position: Vec2, radius: f32, color: Color) {
+	game.render.sdf(SignedDistance::Circle {
+		center: game.camera.to_screen(position),
+		radius: game.camera.pixels(radius * MUR as f32),
+		color,
+	})
+}
+
+fn line(game: &Game, start: Vec2, end: Vec2, color: Color) {
+	game.render.sdf(SignedDistance::LineSegment {
+		start: game.camera.to_screen(start),
+		end: game.camera.to_screen(end),
+		thickness: 2,
+		color,
+	})
+}
+
+/// The circle [Colideable::colides_with] and physics use
+fn bounds<C: Colideable>(game: &Game, thing: &C) {
+	let bounds = thing.bounds();
+	circle(
+		game,
+		bounds.position,
+		bounds.radius,
+		game.theme.debug_bounds,
+	);
+}
+
+/// The smaller circle a bullet has to cross to hit, like in
+/// [Hittable::hit_at]
+fn hit_bounds<H: Hittable>(game: &Game, thing: &H) {
+	let bounds = thing.bounds();
+	circle(
+		game,
+		bounds.position,
+		bounds.radius / 2.0,
+		game.theme.debug_hit,
+	);
+}
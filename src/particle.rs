@@ -0,0 +1,252 @@
+use std::time::Duration;
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use smitten::{Color, SignedDistance, Vec2};
+
+use crate::{theme::Theme, util::Cooldown, Game, MUR};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Shape {
+	Circle,
+	Square,
+}
+
+#[derive(Copy, Clone, Debug)]
+pub struct Particle {
+	pub position: Vec2,
+	pub velocity: Vec2,
+	pub lifetime: Cooldown,
+	/// Color at birth and at death
+	pub colors: (Color, Color),
+	/// Size in world units at birth and at death
+	pub sizes: (f32, f32),
+	pub shape: Shape,
+	/// How much velocity is kept after a second
+	pub drag: f32,
+}
+
+impl Particle {
+	pub fn color(&self) -> Color {
+		let (a, b) = self.colors;
+		let p = self.lifetime.percent();
+
+		Color {
+			r: a.r + (b.r - a.r) * p,
+			g: a.g + (b.g - a.g) * p,
+			b: a.b + (b.b - a.b) * p,
+			a: a.a + (b.a - a.a) * p,
+		}
+	}
+
+	pub fn size(&self) -> f32 {
+		let (a, b) = self.sizes;
+		a + (b - a) * self.lifetime.percent()
+	}
+
+	fn tick(&mut self, delta: Duration) {
+		let dsec = delta.as_secs_f32();
+
+		self.lifetime.subtract(delta);
+		self.position += self.velocity * dsec;
+		self.velocity *= self.drag.powf(dsec);
+	}
+}
+
+/// Every particle lives in one pool that's never shrunk. The first `alive` are
+/// the ones still going, and the rest are spent and get written over when
+/// something new spawns.
+pub struct Particles {
+	pool: Vec<Particle>,
+	alive: usize,
+	/// Particles get their own randomness so they don't change how a seeded
+	/// run plays out
+	rng: StdRng,
+}
+
+impl Particles {
+	/// Past this, new particles are dropped
+	const MAX_PARTICLES: usize = 2048;
+	/// Most particles drawn in a frame
+	const DRAW_CAP: usize = 768;
+
+	pub fn new(seed: u64) -> Self {
+		Self {
+			pool: Vec::with_capacity(Self::MAX_PARTICLES),
+			alive: 0,
+			rng: StdRng::seed_from_u64(seed),
+		}
+	}
+
+	pub fn alive(&self) -> &[Particle] {
+		&self.pool[..self.alive]
+	}
+
+	fn spawn(&mut self, particle: Particle) {
+		if self.alive < self.pool.len() {
+			self.pool[self.alive] = particle;
+		} else if self.pool.len() < Self::MAX_PARTICLES {
+			self.pool.push(particle);
+		} else {
+			return;
+		}
+
+		self.alive += 1;
+	}
+
+	/// Burst `emitter` at `position`. Particles go out around `direction`
+	/// if there is one, otherwise every which way.
+	pub fn emit(&mut self, emitter: &Emitter, position: Vec2, direction: Option<Vec2>) {
+		for _ in 0..emitter.count {
+			let angle = match direction {
+				Some(dir) => dir.angle() + self.rng.gen_range(-emitter.spread..=emitter.spread),
+				None => self.rng.gen_range(0.0..360.0),
+			};
+			let speed = self.rng.gen_range(emitter.speed.0..=emitter.speed.1);
+			let life = self.rng.gen_range(emitter.lifetime.0..=emitter.lifetime.1);
+
+			self.spawn(Particle {
+				position,
+				velocity: Vec2::from_degrees(angle) * speed,
+				lifetime: Cooldown::waiting(Duration::from_millis(life)),
+				colors: emitter.colors,
+				sizes: emitter.sizes,
+				shape: emitter.shape,
+				drag: emitter.drag,
+			});
+		}
+	}
+
+	pub fn tick(&mut self, delta: Duration) {
+		let mut idx = 0;
+		while idx < self.alive {
+			self.pool[idx].tick(delta);
+
+			if self.pool[idx].lifetime.is_ready() {
+				// Swap the spent one out past the end of the living
+				self.alive -= 1;
+				self.pool.swap(idx, self.alive);
+			} else {
+				idx += 1;
+			}
+		}
+	}
+
+	pub fn draw(&self, game: &Game) {
+		for particle in self.alive().iter().take(Self::DRAW_CAP) {
+			let size = particle.size();
+
+			match particle.shape {
+				Shape::Circle => game.render.sdf(SignedDistance::Circle {
+					center: game.camera.to_screen(particle.position),
+					radius: game.camera.pixels(size * MUR as f32 / 2.0),
+					color: particle.color(),
+				}),
+				Shape::Square => {
+					game.rect(particle.position, Vec2::new(size, size), particle.color())
+				}
+			}
+		}
+	}
+}
+
+/// A recipe for a burst of particles
+#[derive(Copy, Clone, Debug)]
+pub struct Emitter {
+	pub count: u32,
+	/// Least and most speed, in world units per second
+	pub speed: (f32, f32),
+	/// How far off the direction a particle can go, in degrees
+	pub spread: f32,
+	/// Shortest and longest life, in milliseconds
+	pub lifetime: (u64, u64),
+	pub colors: (Color, Color),
+	pub sizes: (f32, f32),
+	pub shape: Shape,
+	pub drag: f32,
+}
+
+impl Emitter {
+	/// Sparks off whatever a bullet hit
+	pub fn impact(theme: &Theme) -> Self {
+		Self {
+			count: 6,
+			speed: (3.0, 8.0),
+			spread: 50.0,
+			lifetime: (80, 180),
+			colors: (theme.spark, faded(theme.ember)),
+			sizes: (0.12, 0.04),
+			shape: Shape::Circle,
+			drag: 0.05,
+		}
+	}
+
+	pub fn blood(color: Color) -> Self {
+		Self {
+			count: 5,
+			speed: (1.0, 4.0),
+			spread: 35.0,
+			lifetime: (200, 400),
+			colors: (color, faded(color)),
+			sizes: (0.15, 0.08),
+			shape: Shape::Square,
+			drag: 0.02,
+		}
+	}
+
+	/// An enemy coming apart
+	pub fn death(color: Color) -> Self {
+		Self {
+			count: 16,
+			speed: (1.5, 5.0),
+			lifetime: (300, 600),
+			sizes: (0.25, 0.1),
+			..Self::blood(color)
+		}
+	}
+
+	pub fn explosion(theme: &Theme) -> Self {
+		Self {
+			count: 40,
+			speed: (2.0, 12.0),
+			spread: 180.0,
+			lifetime: (250, 700),
+			colors: (theme.flame, faded(theme.smoke)),
+			sizes: (0.35, 0.6),
+			shape: Shape::Circle,
+			drag: 0.01,
+		}
+	}
+
+	/// Chunks of a wall that's come down
+	pub fn debris(theme: &Theme) -> Self {
+		Self {
+			count: 12,
+			speed: (1.0, 4.0),
+			spread: 180.0,
+			lifetime: (300, 600),
+			colors: (theme.debris, faded(theme.debris)),
+			sizes: (0.2, 0.1),
+			shape: Shape::Square,
+			drag: 0.02,
+		}
+	}
+
+	pub fn muzzle_flash(theme: &Theme) -> Self {
+		Self {
+			count: 4,
+			speed: (4.0, 9.0),
+			spread: 15.0,
+			lifetime: (30, 70),
+			colors: (theme.muzzle_flash, faded(theme.ember)),
+			sizes: (0.2, 0.05),
+			shape: Shape::Circle,
+			drag: 0.001,
+		}
+	}
+}
+
+/// The same color, but see-through, for particles to fade out to
+fn faded(mut color: Color) -> Color {
+	color.a = 0.0;
+	color
+}
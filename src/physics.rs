@@ -0,0 +1,112 @@
+use std::time::Duration;
+
+use smitten::Vec2;
+
+use crate::{traits::Colideable, util::dot};
+
+/// Velocity and mass for things that can be shoved around. Impulses change
+/// the velocity, friction bleeds it off, and `step` says how far to move.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Body {
+	pub velocity: Vec2,
+	pub mass: f32,
+	/// How quickly velocity decays, per second
+	pub friction: f32,
+}
+
+impl Body {
+	pub const FRICTION: f32 = 8.0;
+
+	pub fn new(mass: f32) -> Self {
+		Self {
+			velocity: Vec2::ZERO,
+			mass,
+			friction: Body::FRICTION,
+		}
+	}
+
+	/// Heavier bodies change speed less for the same impulse
+	pub fn apply_impulse(&mut self, impulse: Vec2) {
+		self.velocity += impulse / self.mass;
+	}
+
+	/// How far to move this tick. Friction is applied afterwards.
+	pub fn step(&mut self, delta: Duration) -> Vec2 {
+		let dsec = delta.as_secs_f32();
+		let movement = self.velocity * dsec;
+
+		self.velocity *= (-self.friction * dsec).exp();
+		if self.velocity.length() < 0.01 {
+			self.velocity = Vec2::ZERO;
+		}
+
+		movement
+	}
+
+	/// Drop any velocity heading into a surface with the given normal, which
+	/// points away from the surface.
+	pub fn stop_against(&mut self, normal: Vec2) {
+		let into = dot(self.velocity, normal);
+		if into < 0.0 {
+			self.velocity -= normal * into;
+		}
+	}
+}
+
+pub trait Physical: Colideable {
+	fn body(&self) -> &Body;
+	fn body_mut(&mut self) -> &mut Body;
+
+	fn integrate(&mut self, delta: Duration) {
+		let movement = self.body_mut().step(delta);
+		*self.position_mut() += movement;
+	}
+}
+
+/// How hard overlapping bodies push each other apart, per unit of overlap
+const STIFFNESS: f32 = 20.0;
+
+/// Shove two overlapping bodies apart with equal and opposite impulses, so the
+/// lighter one ends up moving more. Returns if they were overlapping.
+pub fn separate<A: Physical, B: Physical>(a: &mut A, b: &mut B) -> bool {
+	let abound = a.bounds();
+	let bbound = b.bounds();
+
+	let dist = abound.position.distance_with(bbound.position);
+	if dist >= abound.radius {
+		return false;
+	}
+
+	let dir = (bbound.position - abound.position).normalize_correct();
+	let overlap = abound.radius - dist;
+
+	let (amass, bmass) = (a.body().mass, b.body().mass);
+	let reduced_mass = (amass * bmass) / (amass + bmass);
+	let impulse = dir * (overlap * STIFFNESS * reduced_mass);
+
+	a.body_mut().apply_impulse(impulse * -1.0);
+	b.body_mut().apply_impulse(impulse);
+	true
+}
+
+/// Push `b` out of `a`, which doesn't move, and stop it moving any further in.
+/// Returns if they were overlapping.
+pub fn collide_static<A: Colideable, B: Physical>(a: &A, b: &mut B) -> bool {
+	let abound = a.bounds();
+	let bbound = b.bounds();
+
+	let dist = abound.position.distance_with(bbound.position);
+
+	if dist < abound.radius {
+		let dir = abound.position - bbound.position;
+		//desired sepration
+		let wanted = dir.normalize_correct() * (abound.radius - dir.length());
+
+		*b.position_mut() -= wanted;
+		b.body_mut()
+			.stop_against((bbound.position - abound.position).normalize_correct());
+		true
+	} else {
+		false
+	}
+}
@@ -0,0 +1,250 @@
+use std::{
+	cell::RefCell,
+	fs::File,
+	io::{self, BufWriter, Write},
+	path::Path,
+	rc::Rc,
+};
+
+use smitten::{Color, Draw, HorizontalAnchor, SignedDistance, Vec2, VerticalAnchor};
+
+use crate::{
+	render::{Anchors, Render},
+	MUR,
+};
+
+/// How wide a character is, as a part of how tall it is
+const GLYPH_WIDTH: f32 = 0.6;
+
+/// Draws on the CPU into an RGBA buffer, for screenshots and tests where
+/// there's no window or GPU.
+///
+/// There's no font or textures here, so text is a solid block per character
+/// and sprites are a rectangle of their tint. That's enough to see where
+/// everything is.
+///
+/// Clones share the same pixels, so one can be kept to read them back after
+/// the other has been given to a [crate::Game].
+#[derive(Clone)]
+pub struct Raster {
+	width: u32,
+	height: u32,
+	/// Rows from the top, four bytes a pixel
+	pixels: Rc<RefCell<Vec<u8>>>,
+	background: Color,
+}
+
+impl Raster {
+	pub fn new((width, height): (u32, u32)) -> Self {
+		let raster = Self {
+			width,
+			height,
+			pixels: Rc::new(RefCell::new(vec![0; (width * height * 4) as usize])),
+			background: Color::BLACK,
+		};
+		raster.fill_all();
+		raster
+	}
+
+	#[cfg(test)]
+	pub fn size(&self) -> (u32, u32) {
+		(self.width, self.height)
+	}
+
+	/// A copy of what's been drawn, as RGBA rows from the top
+	#[cfg(test)]
+	pub fn pixels(&self) -> Vec<u8> {
+		self.pixels.borrow().clone()
+	}
+
+	pub fn save_png<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+		let mut file = BufWriter::new(File::create(path)?);
+		write_png(&mut file, self.width, self.height, &self.pixels.borrow())?;
+		file.flush()
+	}
+
+	fn fill_all(&self) {
+		let [r, g, b, _] = bytes(self.background);
+		for pixel in self.pixels.borrow_mut().chunks_exact_mut(4) {
+			pixel.copy_from_slice(&[r, g, b, 255]);
+		}
+	}
+
+	/// Where a point in MUR from the middle of the screen is, in pixels from
+	/// the top left
+	fn to_pixels(&self, point: Vec2) -> (f32, f32) {
+		(
+			self.width as f32 / 2.0 + point.x * MUR as f32,
+			self.height as f32 / 2.0 - point.y * MUR as f32,
+		)
+	}
+
+	/// Where the middle of something `dim` big goes with these anchors
+	fn anchored_center(&self, (horizontal, vertical): Anchors, dim: Vec2) -> Vec2 {
+		let half_screen = Vec2::new(
+			self.width as f32 / MUR as f32 / 2.0,
+			self.height as f32 / MUR as f32 / 2.0,
+		);
+
+		let x = match horizontal {
+			HorizontalAnchor::Left(x) => -half_screen.x + x + dim.x / 2.0,
+			HorizontalAnchor::Center(x) => x,
+			HorizontalAnchor::Right(x) => half_screen.x + x - dim.x / 2.0,
+		};
+		let y = match vertical {
+			VerticalAnchor::Top(y) => half_screen.y + y - dim.y / 2.0,
+			VerticalAnchor::Center(y) => y,
+			VerticalAnchor::Bottom(y) => -half_screen.y + y + dim.y / 2.0,
+		};
+
+		Vec2::new(x, y)
+	}
+
+	/// Blend `color` over every pixel between the corners whose middle
+	/// passes `inside`
+	fn shade<F>(&self, top_left: (f32, f32), bottom_right: (f32, f32), color: Color, inside: F)
+	where
+		F: Fn(f32, f32) -> bool,
+	{
+		let left = top_left.0.floor().max(0.0) as u32;
+		let top = top_left.1.floor().max(0.0) as u32;
+		let right = (bottom_right.0.ceil().max(0.0) as u32).min(self.width);
+		let bottom = (bottom_right.1.ceil().max(0.0) as u32).min(self.height);
+
+		let mut pixels = self.pixels.borrow_mut();
+		for y in top..bottom {
+			for x in left..right {
+				if inside(x as f32 + 0.5, y as f32 + 0.5) {
+					let idx = ((y * self.width + x) * 4) as usize;
+					blend(&mut pixels[idx..idx + 4], color);
+				}
+			}
+		}
+	}
+
+	fn fill_rect(&self, center: Vec2, dim: Vec2, color: Color) {
+		let (x, y) = self.to_pixels(center);
+		let half = (dim.x * MUR as f32 / 2.0, dim.y * MUR as f32 / 2.0);
+		let (left, top) = (x - half.0, y - half.1);
+		let (right, bottom) = (x + half.0, y + half.1);
+
+		self.shade((left, top), (right, bottom), color, |px, py| {
+			px >= left && px < right && py >= top && py < bottom
+		});
+	}
+}
+
+impl Render for Raster {
+	fn rect(&self, pos: Vec2, dim: Vec2, draw: Draw) {
+		let color = match draw {
+			Draw::Color(color) => color,
+			Draw::Sprite { tint, .. } => tint,
+			Draw::Texture(_) => Color::WHITE,
+		};
+
+		self.fill_rect(pos, dim, color)
+	}
+
+	fn anchored_rect(&self, anchors: Anchors, dim: Vec2, color: Color) {
+		self.fill_rect(self.anchored_center(anchors, dim), dim, color)
+	}
+
+	fn sdf(&self, sdf: SignedDistance) {
+		match sdf {
+			SignedDistance::Circle {
+				center,
+				radius,
+				color,
+			} => {
+				let (x, y) = self.to_pixels(center);
+				let r = radius as f32;
+
+				self.shade((x - r, y - r), (x + r, y + r), color, |px, py| {
+					(px - x).powi(2) + (py - y).powi(2) <= r * r
+				});
+			}
+			SignedDistance::LineSegment {
+				start,
+				end,
+				thickness,
+				color,
+			} => {
+				let a = self.to_pixels(start);
+				let b = self.to_pixels(end);
+				let half = (thickness as f32 / 2.0).max(0.5);
+
+				let top_left = (a.0.min(b.0) - half, a.1.min(b.1) - half);
+				let bottom_right = (a.0.max(b.0) + half, a.1.max(b.1) + half);
+				self.shade(top_left, bottom_right, color, |px, py| {
+					distance_to_segment((px, py), a, b) <= half
+				});
+			}
+		}
+	}
+
+	fn write(&self, text: &str, anchors: Anchors, color: Color, scale: f32) {
+		let glyph = Vec2::new(scale * GLYPH_WIDTH, scale);
+		let dim = Vec2::new(glyph.x * text.chars().count() as f32, glyph.y);
+		let center = self.anchored_center(anchors, dim);
+		let left = center.x - dim.x / 2.0;
+
+		for (idx, c) in text.chars().enumerate() {
+			if c.is_whitespace() {
+				continue;
+			}
+
+			let x = left + glyph.x * (idx as f32 + 0.5);
+			self.fill_rect(Vec2::new(x, center.y), glyph * 0.7, color);
+		}
+	}
+
+	fn clear_color(&mut self, color: Color) {
+		self.background = color;
+	}
+
+	fn clear(&mut self) {
+		self.fill_all();
+	}
+}
+
+fn bytes(color: Color) -> [u8; 4] {
+	let byte = |f: f32| (f.clamp(0.0, 1.0) * 255.0).round() as u8;
+	[byte(color.r), byte(color.g), byte(color.b), byte(color.a)]
+}
+
+/// Lay `color` over an opaque pixel
+fn blend(pixel: &mut [u8], color: Color) {
+	let a = color.a.clamp(0.0, 1.0);
+	let [r, g, b, _] = bytes(color);
+
+	for (dst, src) in pixel.iter_mut().zip([r, g, b]) {
+		*dst = (src as f32 * a + *dst as f32 * (1.0 - a)).round() as u8;
+	}
+}
+
+fn distance_to_segment(p: (f32, f32), a: (f32, f32), b: (f32, f32)) -> f32 {
+	let ab = (b.0 - a.0, b.1 - a.1);
+	let ap = (p.0 - a.0, p.1 - a.1);
+	let length = ab.0 * ab.0 + ab.1 * ab.1;
+
+	let t = if length == 0.0 {
+		0.0
+	} else {
+		((ap.0 * ab.0 + ap.1 * ab.1) / length).clamp(0.0, 1.0)
+	};
+
+	let closest = (a.0 + ab.0 * t, a.1 + ab.1 * t);
+	((p.0 - closest.0).powi(2) + (p.1 - closest.1).powi(2)).sqrt()
+}
+
+/// Write 8-bit RGBA rows, top first, as a PNG
+pub fn write_png<W: Write>(w: W, width: u32, height: u32, rgba: &[u8]) -> io::Result<()> {
+	let mut encoder = png::Encoder::new(w, width, height);
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(png::BitDepth::Eight);
+
+	let mut writer = encoder.write_header()?;
+	writer.write_image_data(rgba)?;
+	writer.finish()?;
+	Ok(())
+}
@@ -0,0 +1,98 @@
+use std::{cell::RefCell, rc::Rc};
+
+use smitten::{
+	Color, Draw, FontId, HorizontalAnchor, Key, SignedDistance, Smitten, SmittenEvent, Vec2,
+	VerticalAnchor,
+};
+
+pub type Anchors = (HorizontalAnchor, VerticalAnchor);
+
+/// Somewhere the game can be drawn. Positions are in MUR from the middle of
+/// the screen, like smitten. The window's [Canvas] is one, and
+/// [crate::raster::Raster] draws into memory without a GPU.
+pub trait Render {
+	/// Centered on `pos`
+	fn rect(&self, pos: Vec2, dim: Vec2, draw: Draw);
+	/// Placed from the edges of the screen rather than the middle
+	fn anchored_rect(&self, anchors: Anchors, dim: Vec2, color: Color);
+	/// Circles and line segments. Radii and thickness are in pixels.
+	fn sdf(&self, sdf: SignedDistance);
+	fn write(&self, text: &str, anchors: Anchors, color: Color, scale: f32);
+	fn clear_color(&mut self, color: Color);
+	fn clear(&mut self);
+}
+
+/// The game window. Input and the window itself are handled here, and drawing
+/// goes through the [Canvas] it hands out.
+#[derive(Clone)]
+pub struct Window {
+	smitten: Rc<RefCell<Smitten>>,
+}
+
+impl Window {
+	pub fn new(smitten: Smitten) -> Self {
+		Self {
+			smitten: Rc::new(RefCell::new(smitten)),
+		}
+	}
+
+	/// Something to draw into this window with, writing text in `font`
+	pub fn canvas(&self, font: FontId) -> Canvas {
+		Canvas {
+			smitten: self.smitten.clone(),
+			font,
+		}
+	}
+
+	/// What happened since we last asked
+	pub fn events(&self) -> Vec<SmittenEvent> {
+		self.smitten.borrow_mut().events()
+	}
+
+	pub fn is_key_down(&self, key: Key) -> bool {
+		self.smitten.borrow().is_key_down(key)
+	}
+
+	pub fn set_fullscreen(&self, fullscreen: bool) {
+		self.smitten.borrow_mut().set_fullscreen(fullscreen)
+	}
+
+	/// Show what's been drawn since the last clear
+	pub fn swap(&self) {
+		self.smitten.borrow().swap()
+	}
+}
+
+/// Draws into the [Window], and the font text is written in
+pub struct Canvas {
+	smitten: Rc<RefCell<Smitten>>,
+	font: FontId,
+}
+
+impl Render for Canvas {
+	fn rect(&self, pos: Vec2, dim: Vec2, draw: Draw) {
+		self.smitten.borrow().rect(pos, dim, draw)
+	}
+
+	fn anchored_rect(&self, anchors: Anchors, dim: Vec2, color: Color) {
+		self.smitten.borrow().anchored_rect(anchors, dim, color)
+	}
+
+	fn sdf(&self, sdf: SignedDistance) {
+		self.smitten.borrow().sdf(sdf)
+	}
+
+	fn write(&self, text: &str, anchors: Anchors, color: Color, scale: f32) {
+		self.smitten
+			.borrow()
+			.write(self.font, text, anchors, color, scale)
+	}
+
+	fn clear_color(&mut self, color: Color) {
+		self.smitten.borrow_mut().clear_color(color)
+	}
+
+	fn clear(&mut self) {
+		self.smitten.borrow().clear()
+	}
+}
@@ -0,0 +1,82 @@
+use smitten::Vec2;
+
+use crate::MUR;
+
+/// How much of the world fits in the window as it changes size
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Scaling {
+	/// Always show this many world units top to bottom. Wider windows see
+	/// more to the sides.
+	FixedHeight(f32),
+	/// Always show this many world units side to side
+	FixedWidth(f32),
+	/// Always MUR pixels to a world unit, so bigger windows see more
+	Pixel,
+}
+
+impl Scaling {
+	/// What the settings menu cycles through
+	pub const CHOICES: [Scaling; 3] = [
+		Scaling::FixedHeight(20.0),
+		Scaling::FixedWidth(80.0 / 3.0),
+		Scaling::Pixel,
+	];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Scaling::FixedHeight(_) => "fixed height",
+			Scaling::FixedWidth(_) => "fixed width",
+			Scaling::Pixel => "pixel",
+		}
+	}
+}
+
+/// The window, as far as drawing is concerned. Everything that used to size
+/// itself from DIM asks this instead.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Screen {
+	/// In pixels
+	pub size: (u32, u32),
+	pub scaling: Scaling,
+	pub fullscreen: bool,
+}
+
+impl Screen {
+	/// The 20 units tall that a 1280x960 window always had
+	pub const DEFAULT_SCALING: Scaling = Scaling::CHOICES[0];
+
+	pub fn new(size: (u32, u32)) -> Self {
+		Self {
+			size,
+			scaling: Self::DEFAULT_SCALING,
+			fullscreen: false,
+		}
+	}
+
+	pub fn resize(&mut self, width: u32, height: u32) {
+		// Minimizing gives us a zero sized window on some platforms
+		self.size = (width.max(1), height.max(1));
+	}
+
+	/// Pixels to a world unit
+	pub fn pixels_per_unit(&self) -> f32 {
+		match self.scaling {
+			Scaling::FixedHeight(units) => self.size.1 as f32 / units,
+			Scaling::FixedWidth(units) => self.size.0 as f32 / units,
+			Scaling::Pixel => MUR as f32,
+		}
+	}
+
+	/// How much bigger the world is drawn than one MUR to a unit
+	pub fn world_scale(&self) -> f32 {
+		self.pixels_per_unit() / MUR as f32
+	}
+
+	/// Width and height of the window in MUR, which is what UI is measured in
+	pub fn murs(&self) -> Vec2 {
+		Vec2::new(
+			self.size.0 as f32 / MUR as f32,
+			self.size.1 as f32 / MUR as f32,
+		)
+	}
+}
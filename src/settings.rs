@@ -0,0 +1,230 @@
+use smitten::Key;
+
+use crate::{
+	audio::Volumes,
+	screen::{Scaling, Screen},
+	theme::ThemeChoice,
+};
+
+/// Things the player can change from the pause menu. These live past a
+/// restart.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+	/// From 0 to 1
+	pub volume: f32,
+	/// Per kind of sound, on top of `volume`
+	pub volumes: Volumes,
+	pub bindings: Bindings,
+	/// Show pickup and upgrade alerts in the top right
+	pub show_messages: bool,
+	/// Numbers that float up off whatever gets hurt
+	pub show_damage_numbers: bool,
+	/// Bars over hurt enemies and walls
+	pub show_health_bars: bool,
+	/// Slowly heal while there aren't any enemies around
+	pub regeneration: bool,
+	pub fullscreen: bool,
+	/// How much of the world fits in the window
+	pub scaling: Scaling,
+	/// Which colors to draw everything in
+	pub theme: ThemeChoice,
+	/// Debug stuff, like holding K for score
+	pub developer: bool,
+	/// Draw collision circles, where things are headed, and counters
+	pub debug_overlay: bool,
+}
+
+impl Default for Settings {
+	fn default() -> Self {
+		Self {
+			volume: 1.0,
+			volumes: Volumes::default(),
+			bindings: Bindings::default(),
+			show_messages: true,
+			show_damage_numbers: true,
+			show_health_bars: true,
+			regeneration: true,
+			fullscreen: false,
+			scaling: Screen::DEFAULT_SCALING,
+			theme: ThemeChoice::Default,
+			developer: false,
+			debug_overlay: false,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Action {
+	Up,
+	Down,
+	Left,
+	Right,
+	Fire,
+	PreviousWeapon,
+	NextWeapon,
+}
+
+impl Action {
+	pub const ALL: [Action; 7] = [
+		Action::Up,
+		Action::Down,
+		Action::Left,
+		Action::Right,
+		Action::Fire,
+		Action::PreviousWeapon,
+		Action::NextWeapon,
+	];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Action::Up => "up",
+			Action::Down => "down",
+			Action::Left => "left",
+			Action::Right => "right",
+			Action::Fire => "fire",
+			Action::PreviousWeapon => "previous weapon",
+			Action::NextWeapon => "next weapon",
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Bindings {
+	pub up: Key,
+	pub down: Key,
+	pub left: Key,
+	pub right: Key,
+	pub fire: Key,
+	pub previous_weapon: Key,
+	pub next_weapon: Key,
+}
+
+impl Bindings {
+	/// Keys the game already uses for something. Escape and P pause, 1 to 5
+	/// pick weapons, K is developer score, F11 goes fullscreen, and Space picks
+	/// menu items.
+	pub const RESERVED: [Key; 10] = [
+		Key::Space,
+		Key::Escape,
+		Key::P,
+		Key::Row1,
+		Key::Row2,
+		Key::Row3,
+		Key::Row4,
+		Key::Row5,
+		Key::K,
+		Key::F11,
+	];
+
+	/// Whether `action` can have `key`. The menu doesn't use fire, so it can
+	/// keep Space.
+	pub fn allowed(action: Action, key: Key) -> bool {
+		action == Action::Fire && key == Key::Space || !Self::RESERVED.contains(&key)
+	}
+
+	pub fn key(&self, action: Action) -> Key {
+		match action {
+			Action::Up => self.up,
+			Action::Down => self.down,
+			Action::Left => self.left,
+			Action::Right => self.right,
+			Action::Fire => self.fire,
+			Action::PreviousWeapon => self.previous_weapon,
+			Action::NextWeapon => self.next_weapon,
+		}
+	}
+
+	pub fn key_mut(&mut self, action: Action) -> &mut Key {
+		match action {
+			Action::Up => &mut self.up,
+			Action::Down => &mut self.down,
+			Action::Left => &mut self.left,
+			Action::Right => &mut self.right,
+			Action::Fire => &mut self.fire,
+			Action::PreviousWeapon => &mut self.previous_weapon,
+			Action::NextWeapon => &mut self.next_weapon,
+		}
+	}
+
+	/// Bind `key` to `action`. Whatever already had `key` gets the key
+	/// `action` used to have, so no two actions share one. Returns false and
+	/// changes nothing if that would put a reserved key on either of them.
+	pub fn rebind(&mut self, action: Action, key: Key) -> bool {
+		let old = self.key(action);
+		let swapped = Action::ALL
+			.into_iter()
+			.find(|other| self.key(*other) == key);
+
+		if !Self::allowed(action, key) || swapped.is_some_and(|other| !Self::allowed(other, old)) {
+			return false;
+		}
+
+		if let Some(other) = swapped {
+			*self.key_mut(other) = old;
+		}
+		*self.key_mut(action) = key;
+		true
+	}
+}
+
+impl Default for Bindings {
+	fn default() -> Self {
+		Self {
+			up: Key::W,
+			down: Key::S,
+			left: Key::A,
+			right: Key::D,
+			fire: Key::Space,
+			previous_weapon: Key::Q,
+			next_weapon: Key::E,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn rebinding_swaps_a_taken_key() {
+		let mut bindings = Bindings::default();
+		assert!(bindings.rebind(Action::NextWeapon, Key::Q));
+
+		assert_eq!(bindings.next_weapon, Key::Q);
+		assert_eq!(bindings.previous_weapon, Key::E);
+	}
+
+	#[test]
+	fn rebinding_a_free_key() {
+		let mut bindings = Bindings::default();
+		assert!(bindings.rebind(Action::Fire, Key::F));
+
+		assert_eq!(bindings.fire, Key::F);
+		assert_eq!(
+			Bindings {
+				fire: Key::Space,
+				..bindings
+			},
+			Bindings::default()
+		);
+	}
+
+	#[test]
+	fn reserved_keys_are_refused() {
+		let mut bindings = Bindings::default();
+		for key in [Key::Escape, Key::P, Key::Row3, Key::K, Key::F11] {
+			assert!(!bindings.rebind(Action::Up, key));
+		}
+
+		// Space would stop the menu picking things if up had it, and that
+		// goes for swapping it there too
+		assert!(!bindings.rebind(Action::Up, Key::Space));
+		assert!(!bindings.rebind(Action::Fire, Key::W));
+		assert_eq!(bindings, Bindings::default());
+
+		// Fire can have it back though
+		assert!(bindings.rebind(Action::Fire, Key::F));
+		assert!(bindings.rebind(Action::Fire, Key::Space));
+		assert_eq!(bindings, Bindings::default());
+	}
+}
@@ -0,0 +1,258 @@
+//! Golden image tests. A scene is drawn with the [Raster] and compared to a
+//! PNG in `snapshots/`.
+//!
+//! A snapshot that isn't there fails the test. Set `UPDATE_SNAPSHOTS` to
+//! record every snapshot instead of comparing, and look over the new PNGs
+//! before checking them in. When a comparison fails, what's different is
+//! marked in red in `<name>.diff.png` next to the snapshot.
+
+use std::{
+	fs,
+	io::{self, ErrorKind},
+	path::PathBuf,
+	time::Duration,
+};
+
+use smitten::Vec2;
+
+use crate::{
+	animation::Animator,
+	menu::PauseMenu,
+	physics::Body,
+	thing::{self, Enemy},
+	Achievements, Alert, Atlas, Audio, Cooldown, Effects, Explosion, FloatingText, Game,
+	HighScores, Hud, Raster, Screen, Settings, State, Style, ThemeChoice,
+};
+
+/// Smaller than the window so the tests stay quick
+const SIZE: (u32, u32) = (640, 480);
+const SEED: u64 = 51;
+/// How far apart any channel of a pixel can be before it counts as different
+const TOLERANCE: u8 = 2;
+
+/// A game in the middle of things: enemies on their way in, one of them hurt,
+/// walls and barrels down, an explosion going off, alerts up, and us nearly
+/// dead.
+fn scene(settings: Settings) -> (Game, Raster) {
+	let raster = Raster::new(SIZE);
+	let mut game = Game::new(
+		Box::new(raster.clone()),
+		settings,
+		HighScores::default(),
+		Achievements::default(),
+		Audio::default(),
+		Screen::new(SIZE),
+		Atlas::empty(),
+	)
+	.with_seed(SEED);
+
+	// The built in layout, even if there's a custom one on this machine
+	game.hud = Hud::parse(include_str!("../hud.txt"));
+
+	game.player.position = Vec2::new(-1.0, -0.5);
+	game.player.facing = Vec2::new(1.0, 0.0);
+	game.player.health = 6.0;
+
+	let enemy = |position: Vec2, health: f32| Enemy {
+		position,
+		color: game.theme.enemy,
+		health,
+		max_health: 25.0,
+		speed: 0.75,
+		cooldown: Cooldown::ready(Duration::from_secs(2)),
+		should_move_next_frame: true,
+		body: Body::new(1.0),
+		effects: Effects::default(),
+		last_hit: None,
+		animation: Animator::new("enemy_walk"),
+		bonus: false,
+	};
+	let enemies = [
+		enemy(Vec2::new(4.0, 3.0), 25.0),
+		enemy(Vec2::new(5.0, -2.0), 10.0),
+		enemy(Vec2::new(-6.0, 4.0), 25.0),
+		// Off the screen, for an arrow at the edge
+		enemy(Vec2::new(-30.0, 0.0), 25.0),
+	];
+	game.enemies.extend(enemies);
+
+	game.walls.extend([
+		thing::Wall {
+			position: Vec2::new(1.0, 1.0),
+			health: thing::Wall::WALL_HEALTH,
+		},
+		thing::Wall {
+			position: Vec2::new(1.0, 0.0),
+			health: thing::Wall::WALL_HEALTH / 3.0,
+		},
+	]);
+	game.barrels.extend([
+		thing::Barrel::new(Vec2::new(-3.0, -3.0)),
+		thing::Barrel::new(Vec2::new(3.0, -4.0)),
+	]);
+
+	let mut cooldown = Cooldown::waiting(Duration::from_millis(100));
+	cooldown.subtract(Duration::from_millis(50));
+	game.explosions.push(Explosion {
+		position: Vec2::new(-4.0, 1.0),
+		starting_radius: 16.0,
+		ending_radius: 3.0 * crate::MUR as f32,
+		cooldown,
+	});
+
+	game.floating
+		.push(FloatingText::new(Vec2::new(5.0, -2.0), 15.0, Style::Hit));
+
+	let theme = game.theme;
+	game.push_alert(Alert::with_color(String::from("uzi ammo"), theme.alert));
+	game.push_alert(Alert::with_color(
+		String::from("shotgun"),
+		theme.upgrade_alert,
+	));
+	game.push_alert(Alert::with_color(
+		String::from("achievement: first blood"),
+		theme.achievement_alert,
+	));
+
+	(game, raster)
+}
+
+fn path(name: &str) -> PathBuf {
+	PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+		.join("snapshots")
+		.join(name)
+}
+
+/// Draw `state` and compare it to the snapshot called `name`
+fn check(name: &str, game: &mut Game, raster: &Raster, state: State) {
+	game.render.clear();
+	state.draw(game);
+
+	let expected_path = path(&format!("{name}.png"));
+	let diff_path = path(&format!("{name}.diff.png"));
+
+	let update = std::env::var_os("UPDATE_SNAPSHOTS").is_some();
+	let expected = match fs::read(&expected_path) {
+		Ok(_) if update => None,
+		Ok(bytes) => Some(read_png(&bytes).expect("snapshot isn't a PNG we can read")),
+		Err(e) if e.kind() == ErrorKind::NotFound && update => None,
+		Err(e) if e.kind() == ErrorKind::NotFound => panic!(
+			"there's no snapshot at {}, run with UPDATE_SNAPSHOTS=1 to record it",
+			expected_path.display()
+		),
+		Err(e) => panic!("couldn't read {}: {e}", expected_path.display()),
+	};
+
+	let expected = match expected {
+		Some(expected) => expected,
+		None => {
+			fs::create_dir_all(expected_path.parent().unwrap()).unwrap();
+			raster.save_png(&expected_path).unwrap();
+			eprintln!("recorded {}", expected_path.display());
+			return;
+		}
+	};
+
+	let actual = raster.pixels();
+	let (width, height) = raster.size();
+	assert_eq!(
+		(expected.0, expected.1),
+		(width, height),
+		"{name} is a different size than its snapshot"
+	);
+
+	let (different, diff) = compare(&expected.2, &actual);
+	if different == 0 {
+		let _ = fs::remove_file(&diff_path);
+		return;
+	}
+
+	let mut file = fs::File::create(&diff_path).unwrap();
+	crate::raster::write_png(&mut file, width, height, &diff).unwrap();
+	panic!(
+		"{name}: {different} pixels differ from the snapshot, see {}",
+		diff_path.display()
+	);
+}
+
+/// How many pixels are further apart than [TOLERANCE], and an image of
+/// `actual` faded out with those pixels in red
+fn compare(expected: &[u8], actual: &[u8]) -> (usize, Vec<u8>) {
+	let mut different = 0;
+	let mut diff = Vec::with_capacity(actual.len());
+
+	for (want, got) in expected.chunks_exact(4).zip(actual.chunks_exact(4)) {
+		let off = want
+			.iter()
+			.zip(got)
+			.any(|(want, got)| want.abs_diff(*got) > TOLERANCE);
+
+		if off {
+			different += 1;
+			diff.extend([255, 0, 0, 255]);
+		} else {
+			diff.extend(got[..3].iter().map(|c| c / 4 + 191));
+			diff.push(255);
+		}
+	}
+
+	(different, diff)
+}
+
+/// An 8-bit RGBA PNG's size and pixels. Anything else is an error.
+fn read_png(bytes: &[u8]) -> io::Result<(u32, u32, Vec<u8>)> {
+	let mut reader = png::Decoder::new(bytes).read_info()?;
+	let mut rgba = vec![0; reader.output_buffer_size()];
+	let info = reader.next_frame(&mut rgba)?;
+
+	if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+		return Err(io::Error::new(ErrorKind::InvalidData, "not 8-bit RGBA"));
+	}
+
+	rgba.truncate(info.buffer_size());
+	Ok((info.width, info.height, rgba))
+}
+
+#[test]
+fn png_round_trips() {
+	let pixels: Vec<u8> = (0..3 * 2 * 4).map(|b| b as u8 * 10).collect();
+	let mut png = vec![];
+	crate::raster::write_png(&mut png, 3, 2, &pixels).unwrap();
+
+	assert_eq!(read_png(&png).unwrap(), (3, 2, pixels));
+}
+
+#[test]
+fn playing() {
+	let (mut game, raster) = scene(Settings::default());
+	check("playing", &mut game, &raster, State::Playing);
+}
+
+#[test]
+fn paused() {
+	let (mut game, raster) = scene(Settings::default());
+	check(
+		"paused",
+		&mut game,
+		&raster,
+		State::Paused(PauseMenu::default()),
+	);
+}
+
+/// With the atlas the game ships, so the sprites are drawn instead of shapes
+#[test]
+fn sprites() {
+	let (mut game, raster) = scene(Settings::default());
+	game.atlas = Atlas::without_image(include_str!("../atlas.txt"));
+	check("sprites", &mut game, &raster, State::Playing);
+}
+
+#[test]
+fn colorblind() {
+	let settings = Settings {
+		theme: ThemeChoice::Colorblind,
+		..Settings::default()
+	};
+	let (mut game, raster) = scene(settings);
+	check("colorblind", &mut game, &raster, State::Playing);
+}
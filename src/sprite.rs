@@ -0,0 +1,261 @@
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+use smitten::{Color, Draw, Smitten, TextureId, Vec2};
+
+use crate::{
+	animation::{Animator, Clip},
+	Game,
+};
+
+const DEFINITIONS: &str = "atlas.txt";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sprite {
+	/// Top left and bottom right of the sprite in the atlas, from 0 to 1
+	pub region: (Vec2, Vec2),
+	/// How big the sprite is drawn, in world units
+	pub size: Vec2,
+	/// Turn to match the direction the entity is facing
+	pub rotates: bool,
+}
+
+impl Sprite {
+	fn parse(words: &[&str], atlas_size: (f32, f32)) -> Option<Self> {
+		let number = |idx: usize| words.get(idx)?.parse::<f32>().ok();
+		let (x, y, w, h) = (number(0)?, number(1)?, number(2)?, number(3)?);
+		let size = Vec2::new(number(4)?, number(5)?);
+
+		let rotates = match words.get(6) {
+			None => false,
+			Some(&"rotates") => true,
+			Some(_) => return None,
+		};
+
+		Some(Self {
+			region: (
+				Vec2::new(x / atlas_size.0, y / atlas_size.1),
+				Vec2::new((x + w) / atlas_size.0, (y + h) / atlas_size.1),
+			),
+			size,
+			rotates,
+		})
+	}
+}
+
+/// The atlas texture and every sprite in it.
+///
+/// It's described by `atlas.txt` in the working directory, like the font. The
+/// first line names the image and its size in pixels, and every line after is
+/// a sprite:
+///
+/// ```text
+/// atlas atlas.png 256 256
+/// # name  x y w h  world-width world-height  [rotates]
+/// player  0 0 32 32  0.75 0.75  rotates
+/// barrel  32 0 32 32  1 1
+/// # clip name  milliseconds-per-frame  loop|once|hold  frames...
+/// clip enemy_die  80 once  enemy_die_0 enemy_die_1 enemy_die_2
+/// ```
+///
+/// Sprites marked `rotates` turn to face the way their entity is facing. If
+/// there's no atlas, or it doesn't have a sprite for something, that thing is
+/// drawn with the plain shapes it always was.
+///
+/// The clips the game plays are player_idle, player_walk, player_shoot,
+/// player_hurt, enemy_walk, enemy_attack, enemy_die and barrel_explode.
+pub struct Atlas {
+	/// Only None when there's no image, which draws sprites as their tint
+	texture: Option<TextureId>,
+	sprites: HashMap<String, Sprite>,
+	clips: HashMap<String, Clip>,
+}
+
+impl Atlas {
+	/// An atlas with no art, so everything falls back to shapes
+	pub fn empty() -> Self {
+		Self {
+			texture: None,
+			sprites: HashMap::new(),
+			clips: HashMap::new(),
+		}
+	}
+
+	/// Read `atlas.txt` and upload its image. Empty if either isn't there.
+	pub fn load(smitten: &mut Smitten) -> Self {
+		let definitions = match fs::read_to_string(DEFINITIONS) {
+			Ok(definitions) => definitions,
+			Err(_) => return Self::empty(),
+		};
+
+		let (image, mut atlas) = match Self::parse(&definitions) {
+			Some(parsed) => parsed,
+			None => return Self::empty(),
+		};
+
+		if !Path::new(image).is_file() {
+			eprintln!("{DEFINITIONS} wants {image}, which isn't there. Drawing shapes instead.");
+			return Self::empty();
+		}
+
+		atlas.texture = Some(smitten.make_texture(image));
+		atlas
+	}
+
+	/// The sprites and clips in `definitions` without uploading the image.
+	/// Sprites are drawn as a rectangle of their tint, which is all the
+	/// [crate::Raster] would draw of them anyway.
+	#[cfg(test)]
+	pub fn without_image(definitions: &str) -> Self {
+		Self::parse(definitions).unwrap().1
+	}
+
+	/// The image the definitions name, and every sprite and clip in them
+	/// without a texture yet
+	fn parse(definitions: &str) -> Option<(&str, Self)> {
+		let mut lines = definitions
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+		let header: Vec<&str> = lines.next()?.split_whitespace().collect();
+		let (image, atlas_size) = match header[..] {
+			["atlas", image, width, height] => (image, (width.parse().ok()?, height.parse().ok()?)),
+			_ => {
+				eprintln!("{DEFINITIONS} should start with: atlas <image> <width> <height>");
+				return None;
+			}
+		};
+
+		let mut sprites = HashMap::new();
+		let mut clips = HashMap::new();
+		for line in lines {
+			let words: Vec<&str> = line.split_whitespace().collect();
+
+			if let ["clip", name, rest @ ..] = &words[..] {
+				match Clip::parse(rest) {
+					Some(clip) => {
+						clips.insert(name.to_string(), clip);
+					}
+					None => eprintln!("Couldn't understand clip: {line}"),
+				}
+				continue;
+			}
+
+			match Sprite::parse(&words[1..], atlas_size) {
+				Some(sprite) => {
+					sprites.insert(words[0].to_owned(), sprite);
+				}
+				None => eprintln!("Couldn't understand sprite: {line}"),
+			}
+		}
+
+		let atlas = Self {
+			texture: None,
+			sprites,
+			clips,
+		};
+		Some((image, atlas))
+	}
+
+	pub fn get(&self, name: &str) -> Option<&Sprite> {
+		self.sprites.get(name)
+	}
+
+	pub fn clip(&self, name: &str) -> Option<&Clip> {
+		self.clips.get(name)
+	}
+
+	/// How long a clip takes to play through, if we have it
+	pub fn clip_length(&self, name: &str) -> Option<Duration> {
+		self.clip(name).map(Clip::length)
+	}
+
+	/// Whether the animator's clip has played out. Clips we don't have are
+	/// always finished.
+	pub fn is_finished(&self, animator: &Animator) -> bool {
+		match self.clip(animator.clip()) {
+			None => true,
+			Some(clip) => clip.is_finished(animator.elapsed()),
+		}
+	}
+
+	/// Draw the frame the animator is on. Returns false if there's nothing to
+	/// draw, so the caller can fall back to a still sprite or a shape.
+	pub fn draw_animated(
+		&self,
+		game: &Game,
+		animator: &Animator,
+		position: Vec2,
+		facing: Vec2,
+		tint: Color,
+	) -> bool {
+		let frame = self
+			.clip(animator.clip())
+			.and_then(|clip| clip.frame(animator.elapsed()));
+
+		match frame {
+			None => false,
+			Some(frame) => self.draw(game, frame, position, facing, tint),
+		}
+	}
+
+	/// Draw the sprite called `name` centered on `position`. Returns false if
+	/// there's no such sprite so the caller can draw a shape instead.
+	pub fn draw(&self, game: &Game, name: &str, position: Vec2, facing: Vec2, tint: Color) -> bool {
+		let sprite = match self.get(name) {
+			Some(sprite) => sprite,
+			None => return false,
+		};
+
+		let rotation = if sprite.rotates && facing != Vec2::ZERO {
+			facing.angle()
+		} else {
+			0.0
+		};
+
+		let draw = match self.texture {
+			Some(texture) => Draw::Sprite {
+				texture,
+				region: sprite.region,
+				rotation,
+				tint,
+			},
+			None => Draw::Color(tint),
+		};
+		game.rect(position, sprite.size, draw);
+
+		true
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn shipped_atlas_has_every_clip() {
+		let (image, atlas) = Atlas::parse(include_str!("../atlas.txt")).unwrap();
+		assert!(Path::new(env!("CARGO_MANIFEST_DIR")).join(image).is_file());
+
+		let clips = [
+			"player_idle",
+			"player_walk",
+			"player_shoot",
+			"player_hurt",
+			"enemy_walk",
+			"enemy_attack",
+			"enemy_die",
+			"barrel_explode",
+		];
+		for name in clips {
+			let clip = atlas.clip(name).unwrap();
+			for frame in &clip.frames {
+				assert!(atlas.get(frame).is_some(), "{name} has no sprite {frame}");
+			}
+		}
+
+		for name in ["player", "enemy", "bullet"] {
+			assert!(atlas.get(name).is_some(), "no {name} sprite");
+		}
+	}
+}
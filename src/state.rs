@@ -0,0 +1,337 @@
+use smitten::{Color, HorizontalAnchor, Key, SmittenEvent, Vec2, VerticalAnchor};
+
+use crate::{
+	highscore::{HighScores, Run},
+	menu::{Choice, PauseMenu},
+	Game,
+};
+
+/// Where we are in the game. Every state takes care of its own input and
+/// drawing.
+#[derive(Clone, Debug, PartialEq)]
+pub enum State {
+	Title,
+	/// Every achievement, locked or not
+	Achievements,
+	Playing,
+	Paused(PauseMenu),
+	GameOver {
+		score: f32,
+		wave: u32,
+		/// The name being typed for the high score table, if we made it on
+		name: Option<String>,
+	},
+}
+
+/// What the main loop should do after a frame
+pub enum Transition {
+	Stay,
+	To(State),
+	/// Throw away the current game and start playing a new one
+	Restart,
+	Quit,
+}
+
+impl State {
+	pub fn frame(&mut self, game: &mut Game, events: &[SmittenEvent]) -> Transition {
+		match self {
+			State::Title => {
+				game.freeze();
+
+				match released(events) {
+					Some(Key::Space) => Transition::Restart,
+					Some(Key::H) => Transition::To(State::Achievements),
+					Some(Key::Escape) => Transition::Quit,
+					_ => Transition::Stay,
+				}
+			}
+			State::Achievements => {
+				game.freeze();
+
+				match released(events) {
+					Some(Key::Escape) | Some(Key::H) => Transition::To(State::Title),
+					_ => Transition::Stay,
+				}
+			}
+			State::Playing => {
+				match released(events) {
+					Some(Key::P) | Some(Key::Escape) => {
+						return Transition::To(State::Paused(PauseMenu::default()))
+					}
+					_ => (),
+				}
+
+				game.play(events);
+				game.tick();
+
+				if game.player.health <= 0.0 {
+					let name = game.scores.qualifies(game.score).then(String::new);
+
+					Transition::To(State::GameOver {
+						score: game.score,
+						wave: game.stats.waves,
+						name,
+					})
+				} else {
+					Transition::Stay
+				}
+			}
+			State::Paused(menu) => {
+				// Nothing moves while the menu is open
+				game.freeze();
+
+				match menu.input(&mut game.settings, events) {
+					None => Transition::Stay,
+					Some(Choice::Resume) => Transition::To(State::Playing),
+					Some(Choice::Restart) => Transition::Restart,
+					Some(Choice::QuitToTitle) => Transition::To(State::Title),
+				}
+			}
+			State::GameOver {
+				name: Some(name), ..
+			} => {
+				game.freeze();
+
+				for key in events.iter().filter_map(|e| match e {
+					SmittenEvent::Keyup { key, .. } => *key,
+					_ => None,
+				}) {
+					if key == Key::Escape {
+						*self = State::GameOver {
+							score: game.score,
+							wave: game.stats.waves,
+							name: None,
+						};
+						return Transition::Stay;
+					} else if key == Key::Enter {
+						let run = Run::now(
+							name.clone(),
+							game.score,
+							game.stats.waves,
+							game.stats.alive,
+							game.seed,
+						);
+						game.scores.insert(run);
+
+						if let Err(e) = game.scores.save() {
+							eprintln!("Couldn't save high scores: {e}");
+						}
+
+						*self = State::GameOver {
+							score: game.score,
+							wave: game.stats.waves,
+							name: None,
+						};
+						return Transition::Stay;
+					} else if key == Key::Backspace {
+						name.pop();
+					} else if let Some(c) = typed(key) {
+						if name.len() < HighScores::NAME_LENGTH {
+							name.push(c);
+						}
+					}
+				}
+
+				Transition::Stay
+			}
+			State::GameOver { name: None, .. } => {
+				game.freeze();
+
+				match released(events) {
+					Some(Key::R) | Some(Key::Space) => Transition::Restart,
+					Some(Key::Escape) => Transition::To(State::Title),
+					Some(Key::J) => {
+						match game.export_stats() {
+							Ok(path) => eprintln!("Wrote stats to {}", path.display()),
+							Err(e) => eprintln!("Couldn't write stats: {e}"),
+						}
+						Transition::Stay
+					}
+					_ => Transition::Stay,
+				}
+			}
+		}
+	}
+
+	pub fn draw(&self, game: &Game) {
+		match self {
+			State::Title => {
+				banner(game, 3.0);
+				write(game, "Roundhead", 1.0, game.theme.text, 1.5);
+				write(game, "space to start", -0.25, game.theme.text, 0.5);
+				write(
+					game,
+					"h for achievements, escape to quit",
+					-0.75,
+					game.theme.text,
+					0.5,
+				);
+
+				for (idx, run) in game.scores.runs().iter().take(5).enumerate() {
+					write(
+						game,
+						&format!(
+							"{}. {} - {} (wave {})",
+							idx + 1,
+							run.name,
+							run.score,
+							run.wave
+						),
+						-2.5 - idx as f32 * 0.5,
+						game.theme.text,
+						0.5,
+					);
+				}
+			}
+			State::Achievements => {
+				let all = game.achievements.all();
+				let top = all.len() as f32 * 0.35;
+
+				write(game, "Achievements", top + 1.0, game.theme.text, 1.0);
+				for (idx, achievement) in all.iter().enumerate() {
+					let (mark, color) = if game.achievements.is_unlocked(achievement) {
+						("[x]", game.theme.unlocked)
+					} else {
+						("[ ]", game.theme.locked)
+					};
+
+					write(
+						game,
+						&format!("{mark} {} - {}", achievement.title, achievement.description),
+						top - idx as f32 * 0.7,
+						color,
+						0.4,
+					);
+				}
+
+				write(
+					game,
+					"escape to go back",
+					top - all.len() as f32 * 0.7 - 0.5,
+					game.theme.text,
+					0.4,
+				);
+			}
+			State::Playing => game.draw(),
+			State::Paused(menu) => {
+				game.draw();
+				menu.draw(game);
+			}
+			State::GameOver { score, wave, name } => {
+				let summary = game.stats.summary();
+
+				game.draw();
+				banner(game, 4.0 + summary.len() as f32);
+				write(game, "You died!", 0.75, game.theme.died, 1.5);
+				write(
+					game,
+					&format!("score {score} - wave {wave}"),
+					-0.25,
+					game.theme.menu_text,
+					0.5,
+				);
+
+				for (idx, line) in summary.iter().enumerate() {
+					write(
+						game,
+						line,
+						-1.5 - idx as f32 * 0.5,
+						game.theme.menu_text,
+						0.4,
+					);
+				}
+
+				match name {
+					Some(name) => write(
+						game,
+						&format!("new high score! name: {name}_"),
+						-0.75,
+						game.theme.highlight,
+						0.5,
+					),
+					None => write(
+						game,
+						"r to restart, j to export stats, escape for title",
+						-0.75,
+						game.theme.menu_text,
+						0.5,
+					),
+				}
+			}
+		}
+	}
+}
+
+/// What a key types into a name, if it types anything
+fn typed(key: Key) -> Option<char> {
+	let c = match key {
+		Key::A => 'a',
+		Key::B => 'b',
+		Key::C => 'c',
+		Key::D => 'd',
+		Key::E => 'e',
+		Key::F => 'f',
+		Key::G => 'g',
+		Key::H => 'h',
+		Key::I => 'i',
+		Key::J => 'j',
+		Key::K => 'k',
+		Key::L => 'l',
+		Key::M => 'm',
+		Key::N => 'n',
+		Key::O => 'o',
+		Key::P => 'p',
+		Key::Q => 'q',
+		Key::R => 'r',
+		Key::S => 's',
+		Key::T => 't',
+		Key::U => 'u',
+		Key::V => 'v',
+		Key::W => 'w',
+		Key::X => 'x',
+		Key::Y => 'y',
+		Key::Z => 'z',
+		Key::Row0 => '0',
+		Key::Row1 => '1',
+		Key::Row2 => '2',
+		Key::Row3 => '3',
+		Key::Row4 => '4',
+		Key::Row5 => '5',
+		Key::Row6 => '6',
+		Key::Row7 => '7',
+		Key::Row8 => '8',
+		Key::Row9 => '9',
+		_ => return None,
+	};
+
+	Some(c)
+}
+
+/// The last key released this frame
+fn released(events: &[SmittenEvent]) -> Option<Key> {
+	events
+		.iter()
+		.filter_map(|e| match e {
+			SmittenEvent::Keyup { key, .. } => *key,
+			_ => None,
+		})
+		.next_back()
+}
+
+/// A dark strip across the middle of the screen for text to sit on
+pub fn banner(game: &Game, height: f32) {
+	game.render.anchored_rect(
+		(HorizontalAnchor::Center(0.0), VerticalAnchor::Center(0.0)),
+		Vec2::new(game.screen.murs().x, height),
+		game.theme.banner,
+	);
+}
+
+pub fn write(game: &Game, text: &str, y: f32, color: Color, scale: f32) {
+	game.render.write(
+		text,
+		(HorizontalAnchor::Center(0.0), VerticalAnchor::Center(y)),
+		color,
+		scale,
+	);
+}
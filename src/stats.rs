@@ -0,0 +1,264 @@
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+
+/// What finished off an enemy
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum KillSource {
+	Weapon(&'static str),
+	/// Which explosion it was, so we can tell how many one barrel got
+	Explosion(u32),
+	Burn,
+}
+
+/// What hurt the player
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DamageSource {
+	Enemy,
+	Explosion,
+	Burn,
+}
+
+impl DamageSource {
+	pub fn name(&self) -> &'static str {
+		match self {
+			DamageSource::Enemy => "enemy",
+			DamageSource::Explosion => "explosion",
+			DamageSource::Burn => "burn",
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct WeaponStats {
+	/// Every bullet counts, so a shotgun blast is a few shots
+	pub shots: u32,
+	pub hits: u32,
+	pub kills: u32,
+}
+
+/// Everything we count over a run
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+	pub weapons: BTreeMap<&'static str, WeaponStats>,
+	pub barrel_kills: u32,
+	/// The most kills a single explosion got
+	pub best_barrel: u32,
+	/// How many explosions there have been. Also used to tell them apart.
+	pub explosions: u32,
+	explosion_kills: BTreeMap<u32, u32>,
+	pub burn_kills: u32,
+	/// How many waves have spawned
+	pub waves: u32,
+	pub walls_placed: u32,
+	pub walls_destroyed: u32,
+	pub damage_taken: BTreeMap<DamageSource, f32>,
+	pub highest_multiplier: f32,
+	pub pickups: u32,
+	/// How long we've been playing, not counting time paused
+	pub alive: Duration,
+}
+
+impl Stats {
+	pub fn weapon(&mut self, name: &'static str) -> &mut WeaponStats {
+		self.weapons.entry(name).or_default()
+	}
+
+	pub fn took(&mut self, source: DamageSource, amount: f32) {
+		if amount > 0.0 {
+			*self.damage_taken.entry(source).or_default() += amount;
+		}
+	}
+
+	pub fn killed(&mut self, source: Option<KillSource>) {
+		match source {
+			Some(KillSource::Weapon(name)) => self.weapon(name).kills += 1,
+			Some(KillSource::Explosion(id)) => {
+				self.barrel_kills += 1;
+
+				let kills = self.explosion_kills.entry(id).or_default();
+				*kills += 1;
+				self.best_barrel = self.best_barrel.max(*kills);
+			}
+			Some(KillSource::Burn) => self.burn_kills += 1,
+			None => (),
+		}
+	}
+
+	pub fn multiplier(&mut self, current: f32) {
+		self.highest_multiplier = self.highest_multiplier.max(current);
+	}
+
+	pub fn kills(&self) -> u32 {
+		let weapon_kills: u32 = self.weapons.values().map(|w| w.kills).sum();
+		weapon_kills + self.barrel_kills + self.burn_kills
+	}
+
+	/// A stat by name, for things like achievements that are written down
+	/// somewhere else
+	pub fn value(&self, name: &str) -> Option<f32> {
+		let shots: u32 = self.weapons.values().map(|w| w.shots).sum();
+		let hits: u32 = self.weapons.values().map(|w| w.hits).sum();
+
+		Some(match name {
+			"kills" => self.kills() as f32,
+			"best_barrel" => self.best_barrel as f32,
+			"barrel_kills" => self.barrel_kills as f32,
+			"burn_kills" => self.burn_kills as f32,
+			"waves" => self.waves as f32,
+			"walls_placed" => self.walls_placed as f32,
+			"walls_destroyed" => self.walls_destroyed as f32,
+			"highest_multiplier" => self.highest_multiplier,
+			"pickups" => self.pickups as f32,
+			"alive" => self.alive.as_secs_f32(),
+			"shots" => shots as f32,
+			"hits" => hits as f32,
+			"accuracy" if shots > 0 => hits as f32 / shots as f32,
+			"accuracy" => 0.0,
+			"damage_taken" => self.damage_taken.values().sum(),
+			_ => return None,
+		})
+	}
+
+	/// Lines for the end of run summary
+	pub fn summary(&self) -> Vec<String> {
+		let mut lines = vec![format!(
+			"alive {:.0}s - best multiplier x{} - {} pickups",
+			self.alive.as_secs_f32(),
+			self.highest_multiplier as usize,
+			self.pickups
+		)];
+
+		for (name, weapon) in &self.weapons {
+			lines.push(format!(
+				"{name}: {}/{} hits, {} kills",
+				weapon.hits, weapon.shots, weapon.kills
+			));
+		}
+
+		lines.push(format!(
+			"barrel kills {} - burn kills {} - walls {} placed, {} lost",
+			self.barrel_kills, self.burn_kills, self.walls_placed, self.walls_destroyed
+		));
+
+		let damage: Vec<String> = self
+			.damage_taken
+			.iter()
+			.map(|(source, amount)| format!("{} {:.1}", source.name(), amount))
+			.collect();
+		if !damage.is_empty() {
+			lines.push(format!("damage taken: {}", damage.join(", ")));
+		}
+
+		lines
+	}
+
+	pub fn to_json(&self) -> String {
+		let mut json = String::from("{");
+
+		// Writing to a String can't fail
+		let _ = write!(
+			json,
+			"\"alive\":{},\"waves\":{},\"highest_multiplier\":{},\"pickups\":{},\
+			\"barrel_kills\":{},\"best_barrel\":{},\"burn_kills\":{},\"walls_placed\":{},\
+			\"walls_destroyed\":{},",
+			self.alive.as_secs_f32(),
+			self.waves,
+			self.highest_multiplier,
+			self.pickups,
+			self.barrel_kills,
+			self.best_barrel,
+			self.burn_kills,
+			self.walls_placed,
+			self.walls_destroyed
+		);
+
+		let weapons: Vec<String> = self
+			.weapons
+			.iter()
+			.map(|(name, w)| {
+				format!(
+					"{}:{{\"shots\":{},\"hits\":{},\"kills\":{}}}",
+					json_string(name),
+					w.shots,
+					w.hits,
+					w.kills
+				)
+			})
+			.collect();
+		let _ = write!(json, "\"weapons\":{{{}}},", weapons.join(","));
+
+		let damage: Vec<String> = self
+			.damage_taken
+			.iter()
+			.map(|(source, amount)| format!("{}:{}", json_string(source.name()), amount))
+			.collect();
+		let _ = write!(json, "\"damage_taken\":{{{}}}", damage.join(","));
+
+		json.push('}');
+		json
+	}
+}
+
+/// Quote a string for JSON, escaping what needs it
+fn json_string(s: &str) -> String {
+	let mut quoted = String::from('"');
+	for c in s.chars() {
+		match c {
+			'"' => quoted.push_str("\\\""),
+			'\\' => quoted.push_str("\\\\"),
+			c if c.is_control() => {
+				let _ = write!(quoted, "\\u{:04x}", c as u32);
+			}
+			c => quoted.push(c),
+		}
+	}
+	quoted.push('"');
+	quoted
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn json_shape() {
+		let mut stats = Stats {
+			waves: 3,
+			highest_multiplier: 2.5,
+			alive: Duration::from_millis(1500),
+			..Stats::default()
+		};
+		*stats.weapon("pistol") = WeaponStats {
+			shots: 4,
+			hits: 3,
+			kills: 1,
+		};
+		stats.took(DamageSource::Enemy, 0.5);
+		stats.took(DamageSource::Burn, 2.0);
+
+		// Whole floats are written without a fraction, which is still a
+		// number to JSON
+		assert_eq!(
+			stats.to_json(),
+			"{\"alive\":1.5,\"waves\":3,\"highest_multiplier\":2.5,\"pickups\":0,\
+			\"barrel_kills\":0,\"best_barrel\":0,\"burn_kills\":0,\"walls_placed\":0,\
+			\"walls_destroyed\":0,\"weapons\":{\"pistol\":{\"shots\":4,\"hits\":3,\"kills\":1}},\
+			\"damage_taken\":{\"enemy\":0.5,\"burn\":2}}"
+		);
+	}
+
+	#[test]
+	fn empty_json() {
+		assert!(Stats::default()
+			.to_json()
+			.ends_with("\"weapons\":{},\"damage_taken\":{}}"));
+	}
+
+	#[test]
+	fn strings_are_escaped() {
+		assert_eq!(json_string("plain"), "\"plain\"");
+		assert_eq!(
+			json_string("a \"b\" \\ c\n"),
+			"\"a \\\"b\\\" \\\\ c\\u000a\""
+		);
+	}
+}
@@ -0,0 +1,197 @@
+use std::time::Duration;
+
+use smitten::Color;
+
+use crate::{theme::Theme, util::Cooldown};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EffectKind {
+	/// Damage over time. `strength` is damage per second, per stack.
+	Burn,
+	/// `strength` is how much of our speed is taken away, from 0 to 1
+	Slow,
+	/// Can't move or attack
+	Stun,
+	/// Damage taken is scaled by `1.0 + strength`
+	Vulnerable,
+}
+
+impl EffectKind {
+	/// What happens when something that already has this effect gets it again
+	pub fn stacking(&self) -> Stacking {
+		match self {
+			EffectKind::Burn => Stacking::Intensify { max: 3 },
+			EffectKind::Slow => Stacking::Strongest,
+			EffectKind::Stun => Stacking::Longest,
+			EffectKind::Vulnerable => Stacking::Strongest,
+		}
+	}
+
+	pub fn tint(&self, theme: &Theme) -> Color {
+		match self {
+			EffectKind::Burn => theme.burn,
+			EffectKind::Slow => theme.slow,
+			EffectKind::Stun => theme.stun,
+			EffectKind::Vulnerable => theme.vulnerable,
+		}
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Stacking {
+	/// Add a stack, up to `max`, and start the duration over
+	Intensify { max: u32 },
+	/// Keep whichever is stronger and start the duration over
+	Strongest,
+	/// Keep whichever has longer left
+	Longest,
+}
+
+/// An effect waiting to be applied to something
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct StatusEffect {
+	pub kind: EffectKind,
+	pub strength: f32,
+	pub duration: Duration,
+}
+
+impl StatusEffect {
+	pub fn new(kind: EffectKind, strength: f32, duration: Duration) -> Self {
+		Self {
+			kind,
+			strength,
+			duration,
+		}
+	}
+
+	pub fn burn(dps: f32, duration: Duration) -> Self {
+		Self::new(EffectKind::Burn, dps, duration)
+	}
+
+	pub fn stun(duration: Duration) -> Self {
+		Self::new(EffectKind::Stun, 1.0, duration)
+	}
+
+	pub fn slow(amount: f32, duration: Duration) -> Self {
+		Self::new(EffectKind::Slow, amount, duration)
+	}
+
+	pub fn vulnerable(amount: f32, duration: Duration) -> Self {
+		Self::new(EffectKind::Vulnerable, amount, duration)
+	}
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Active {
+	effect: StatusEffect,
+	stacks: u32,
+	remaining: Cooldown,
+}
+
+/// Everything currently affecting an enemy or the player
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Effects {
+	active: Vec<Active>,
+}
+
+impl Effects {
+	pub fn apply(&mut self, effect: StatusEffect) {
+		let existing = self
+			.active
+			.iter_mut()
+			.find(|active| active.effect.kind == effect.kind);
+
+		let active = match existing {
+			None => {
+				self.active.push(Active {
+					effect,
+					stacks: 1,
+					remaining: Cooldown::waiting(effect.duration),
+				});
+				return;
+			}
+			Some(active) => active,
+		};
+
+		match effect.kind.stacking() {
+			Stacking::Intensify { max } => {
+				active.stacks = (active.stacks + 1).min(max);
+				active.effect.strength = active.effect.strength.max(effect.strength);
+				active.remaining = Cooldown::waiting(effect.duration);
+			}
+			Stacking::Strongest => {
+				if effect.strength >= active.effect.strength {
+					active.effect = effect;
+				}
+				active.remaining = Cooldown::waiting(effect.duration);
+			}
+			Stacking::Longest => {
+				if effect.duration > active.remaining.remaining() {
+					active.effect = effect;
+					active.remaining = Cooldown::waiting(effect.duration);
+				}
+			}
+		}
+	}
+
+	/// Count down every effect, dropping the ones that ran out. Returns how
+	/// much burn damage to take this tick.
+	pub fn tick(&mut self, delta: Duration) -> f32 {
+		let dsec = delta.as_secs_f32();
+
+		let burn = self
+			.active
+			.iter()
+			.filter(|active| active.effect.kind == EffectKind::Burn)
+			.map(|active| active.effect.strength * active.stacks as f32 * dsec)
+			.sum();
+
+		self.active.retain_mut(|active| {
+			active.remaining.subtract(delta);
+			!active.remaining.is_ready()
+		});
+
+		burn
+	}
+
+	fn strength(&self, kind: EffectKind) -> Option<f32> {
+		self.active
+			.iter()
+			.find(|active| active.effect.kind == kind)
+			.map(|active| active.effect.strength)
+	}
+
+	/// What to multiply our speed by
+	pub fn speed_scale(&self) -> f32 {
+		if self.is_stunned() {
+			return 0.0;
+		}
+
+		1.0 - self
+			.strength(EffectKind::Slow)
+			.unwrap_or(0.0)
+			.clamp(0.0, 1.0)
+	}
+
+	pub fn is_stunned(&self) -> bool {
+		self.strength(EffectKind::Stun).is_some()
+	}
+
+	/// What to multiply damage we take by
+	pub fn damage_scale(&self) -> f32 {
+		1.0 + self.strength(EffectKind::Vulnerable).unwrap_or(0.0)
+	}
+
+	/// `color` mixed with the tint of whatever effect was most recently applied
+	pub fn tint(&self, color: Color, theme: &Theme) -> Color {
+		match self.active.last() {
+			None => color,
+			Some(active) => crate::color_lerp(color, active.effect.kind.tint(theme), 0.5),
+		}
+	}
+}
+
+pub trait Affected {
+	fn effects(&self) -> &Effects;
+	fn effects_mut(&mut self) -> &mut Effects;
+}
@@ -0,0 +1,288 @@
+use std::fs;
+
+use smitten::Color;
+
+use crate::highscore;
+
+const DEFAULT: &str = include_str!("../themes/default.txt");
+const HIGH_CONTRAST: &str = include_str!("../themes/high_contrast.txt");
+const COLORBLIND: &str = include_str!("../themes/colorblind.txt");
+
+/// Which theme to use. Everything but the default is layered on top of it.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ThemeChoice {
+	Default,
+	HighContrast,
+	Colorblind,
+	/// `theme.txt` in the roundhead data directory
+	Custom,
+}
+
+impl ThemeChoice {
+	pub const CHOICES: [ThemeChoice; 4] = [
+		ThemeChoice::Default,
+		ThemeChoice::HighContrast,
+		ThemeChoice::Colorblind,
+		ThemeChoice::Custom,
+	];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			ThemeChoice::Default => "default",
+			ThemeChoice::HighContrast => "high contrast",
+			ThemeChoice::Colorblind => "colorblind",
+			ThemeChoice::Custom => "custom",
+		}
+	}
+}
+
+/// Declares every color in a theme once, so the struct and the lookup by
+/// name can't drift apart
+macro_rules! theme {
+	($($name:ident),* $(,)?) => {
+		/// Every gameplay and UI color. See `themes/default.txt` for what each
+		/// one is and the format of theme files.
+		#[derive(Copy, Clone, Debug, PartialEq)]
+		pub struct Theme {
+			$(pub $name: Color,)*
+		}
+
+		impl Theme {
+			pub const NAMES: &'static [&'static str] = &[$(stringify!($name),)*];
+
+			/// Everything black, for the default theme to be parsed onto
+			fn blank() -> Self {
+				Self {
+					$($name: Color::BLACK,)*
+				}
+			}
+
+			pub fn get(&self, name: &str) -> Option<Color> {
+				match name {
+					$(stringify!($name) => Some(self.$name),)*
+					_ => None,
+				}
+			}
+
+			fn get_mut(&mut self, name: &str) -> Option<&mut Color> {
+				match name {
+					$(stringify!($name) => Some(&mut self.$name),)*
+					_ => None,
+				}
+			}
+		}
+	};
+}
+
+theme!(
+	background,
+	grid_light,
+	grid_dark,
+	room_wall,
+	player,
+	player_facing,
+	player_flash,
+	enemy,
+	bonus_enemy,
+	bullet,
+	barrel,
+	pickup,
+	wall,
+	wall_damaged,
+	explosion,
+	burn,
+	slow,
+	stun,
+	vulnerable,
+	spark,
+	ember,
+	flame,
+	smoke,
+	debris,
+	muzzle_flash,
+	text,
+	menu_text,
+	highlight,
+	banner,
+	panel,
+	wave_timer,
+	health,
+	health_low,
+	health_back,
+	minimap_wall,
+	died,
+	unlocked,
+	locked,
+	alert,
+	upgrade_alert,
+	achievement_alert,
+	damage_hit,
+	damage_crit,
+	damage_explosion,
+	debug_bounds,
+	debug_hit,
+	debug_target,
+	debug_path,
+	debug_velocity,
+	debug_spawn,
+	debug_pickup,
+	debug_text,
+);
+
+impl Theme {
+	pub fn load(choice: ThemeChoice) -> Self {
+		let default = Self::blank().layer(DEFAULT);
+
+		match choice {
+			ThemeChoice::Default => default,
+			ThemeChoice::HighContrast => default.layer(HIGH_CONTRAST),
+			ThemeChoice::Colorblind => default.layer(COLORBLIND),
+			ThemeChoice::Custom => {
+				let custom = highscore::data_dir()
+					.map(|dir| dir.join("roundhead").join("theme.txt"))
+					.and_then(|path| fs::read_to_string(path).ok());
+
+				match custom {
+					Some(custom) => default.layer(&custom),
+					None => {
+						eprintln!("No theme.txt in the data directory, using the default");
+						default
+					}
+				}
+			}
+		}
+	}
+
+	/// This theme with the colors in `file` set over it
+	pub fn layer(mut self, file: &str) -> Self {
+		let lines = file
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+		for line in lines {
+			let parsed = line.split_once('=').and_then(|(name, color)| {
+				let color = parse_color(color.trim())?;
+				let slot = self.get_mut(name.trim())?;
+				*slot = color;
+				Some(())
+			});
+
+			if parsed.is_none() {
+				eprintln!("Couldn't understand theme color: {line}");
+			}
+		}
+
+		self
+	}
+}
+
+impl Default for Theme {
+	fn default() -> Self {
+		Self::load(ThemeChoice::Default)
+	}
+}
+
+/// `#rrggbb`, `#rrggbbaa`, or `r, g, b[, a]` from 0 to 1
+pub fn parse_color(value: &str) -> Option<Color> {
+	if let Some(hex) = value.strip_prefix('#') {
+		let byte = |idx: usize| {
+			let digits = hex.get(idx * 2..idx * 2 + 2)?;
+			u8::from_str_radix(digits, 16).ok()
+		};
+
+		let mut color = Color::rgb8(byte(0)?, byte(1)?, byte(2)?);
+		match hex.len() {
+			6 => (),
+			8 => color.a = byte(3)? as f32 / 255.0,
+			_ => return None,
+		}
+		return Some(color);
+	}
+
+	let parts = value
+		.split(',')
+		.map(|part| part.trim().parse().ok())
+		.collect::<Option<Vec<f32>>>()?;
+
+	match parts[..] {
+		[r, g, b] => Some(Color::rgb(r, g, b)),
+		[r, g, b, a] => Some(Color::rgba(r, g, b, a)),
+		_ => None,
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	/// Every `name = color` line in a theme file
+	fn entries(file: &str) -> Vec<(&str, &str)> {
+		file.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'))
+			.map(|line| {
+				let (name, color) = line.split_once('=').unwrap();
+				(name.trim(), color.trim())
+			})
+			.collect()
+	}
+
+	#[test]
+	fn parses_hex() {
+		assert_eq!(parse_color("#ff8000"), Some(Color::rgb8(255, 128, 0)));
+
+		let mut half = Color::rgb8(0, 0, 255);
+		half.a = 128.0 / 255.0;
+		assert_eq!(parse_color("#0000ff80"), Some(half));
+	}
+
+	#[test]
+	fn parses_floats() {
+		assert_eq!(parse_color("1, 0.5, 0"), Some(Color::rgb(1.0, 0.5, 0.0)));
+		assert_eq!(
+			parse_color("0,0,0,0.25"),
+			Some(Color::rgba(0.0, 0.0, 0.0, 0.25))
+		);
+	}
+
+	#[test]
+	fn rejects_bad_colors() {
+		for bad in [
+			"",
+			"#",
+			"#fff",
+			"#ff80000",
+			"#ff8000ff00",
+			"#gg0000",
+			"1, 0",
+			"1, 0, 0, 0, 0",
+			"red",
+			"1, x, 0",
+		] {
+			assert_eq!(parse_color(bad), None, "{bad:?} parsed");
+		}
+	}
+
+	#[test]
+	fn default_sets_every_color() {
+		let entries = entries(DEFAULT);
+
+		for name in Theme::NAMES {
+			assert!(
+				entries.iter().any(|(set, _)| set == name),
+				"default theme doesn't set {name}"
+			);
+		}
+	}
+
+	#[test]
+	fn builtin_themes_only_set_real_colors() {
+		for file in [DEFAULT, HIGH_CONTRAST, COLORBLIND] {
+			for (name, color) in entries(file) {
+				assert!(Theme::NAMES.contains(&name), "{name} isn't a color");
+				assert!(parse_color(color).is_some(), "{name} = {color}");
+			}
+		}
+	}
+}
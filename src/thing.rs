@@ -1,6 +1,13 @@
+use std::{ops::RangeInclusive, time::Duration};
+
 use smitten::{Color, Vec2};
 
 use crate::{
+	animation::Animator,
+	physics::{Body, Physical},
+	stats::KillSource,
+	status::{Affected, Effects, StatusEffect},
+	theme::Theme,
 	traits::{Colideable, Destructible, Explosive, ExplosiveDetails, Hittable},
 	util::Cooldown,
 	weapon::Bullet,
@@ -12,9 +19,25 @@ pub struct Enemy {
 	pub position: Vec2,
 	pub color: Color,
 	pub health: f32,
+	/// What we spawned with, for the health bar
+	pub max_health: f32,
 	pub speed: f32,
 	pub cooldown: Cooldown,
 	pub should_move_next_frame: bool,
+	pub body: Body,
+	pub effects: Effects,
+	/// Whatever hurt us last, so we know what killed us
+	pub last_hit: Option<KillSource>,
+	pub animation: Animator,
+	/// The one we start with, worth a lot more than the rest
+	pub bonus: bool,
+}
+
+impl Enemy {
+	/// Wave enemies come in a range of weights, so the heavy ones are harder
+	/// to shove around
+	pub const MASS: RangeInclusive<f32> = 0.75..=1.5;
+	pub const BONUS_MASS: f32 = 3.0;
 }
 
 impl Colideable for Enemy {
@@ -30,9 +53,24 @@ impl Colideable for Enemy {
 	}
 }
 
+impl Physical for Enemy {
+	fn body(&self) -> &Body {
+		&self.body
+	}
+
+	fn body_mut(&mut self) -> &mut Body {
+		&mut self.body
+	}
+}
+
 impl Hittable for Enemy {
 	fn hit(&mut self, bullet: &Bullet) {
-		self.health -= bullet.damage;
+		self.damage(bullet.damage);
+		self.last_hit = Some(KillSource::Weapon(bullet.weapon));
+
+		if let Some(effect) = bullet.effect {
+			self.effects.apply(effect);
+		}
 	}
 }
 
@@ -44,6 +82,20 @@ impl Destructible for Enemy {
 	fn health_mut(&mut self) -> &mut f32 {
 		&mut self.health
 	}
+
+	fn vulnerability(&self) -> f32 {
+		self.effects.damage_scale()
+	}
+}
+
+impl Affected for Enemy {
+	fn effects(&self) -> &Effects {
+		&self.effects
+	}
+
+	fn effects_mut(&mut self) -> &mut Effects {
+		&mut self.effects
+	}
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -54,9 +106,15 @@ pub struct Wall {
 
 impl Wall {
 	pub const WALL_HEALTH: f32 = 100.0;
+	/// How much of an explosion makes it through a wall
+	pub const BLAST_PASSTHROUGH: f32 = 0.25;
 
-	pub fn damage_color(&self) -> Color {
-		crate::color_lerp(Color::WHITE, Color::BLACK, self.health / Wall::WALL_HEALTH)
+	pub fn damage_color(&self, theme: &Theme) -> Color {
+		crate::color_lerp(
+			theme.wall,
+			theme.wall_damaged,
+			self.health / Wall::WALL_HEALTH,
+		)
 	}
 }
 
@@ -110,6 +168,38 @@ impl Colideable for Pickup {
 pub struct Barrel {
 	pub position: Vec2,
 	pub health: f32,
+	/// Set when another explosion sets us off. We go up when it runs out.
+	pub fuse: Option<Cooldown>,
+}
+
+impl Barrel {
+	/// How long a barrel caught in an explosion waits before it goes up too
+	pub const CHAIN_DELAY: Duration = Duration::from_millis(120);
+
+	pub fn new(position: Vec2) -> Self {
+		Self {
+			position,
+			health: 1.0,
+			fuse: None,
+		}
+	}
+
+	/// Does nothing if we're already lit
+	pub fn light(&mut self) {
+		if self.fuse.is_none() {
+			self.fuse = Some(Cooldown::waiting(Barrel::CHAIN_DELAY));
+		}
+	}
+
+	pub fn tick(&mut self, delta: Duration) {
+		if let Some(fuse) = self.fuse.as_mut() {
+			fuse.subtract(delta);
+
+			if fuse.is_ready() {
+				self.health = 0.0;
+			}
+		}
+	}
 }
 
 impl Colideable for Barrel {
@@ -144,5 +234,126 @@ impl Destructible for Barrel {
 impl Explosive for Barrel {
 	fn details(&self) -> crate::traits::ExplosiveDetails {
 		ExplosiveDetails::new(25.0, self.position, 3.0)
+			.with_falloff(1.0)
+			// Friction stops a body after it slides `impulse / (mass * friction)`,
+			// so this shoves an average enemy about two units
+			.with_knockback(2.0 * Body::FRICTION)
+			.with_effect(StatusEffect::burn(4.0, Duration::from_secs(2)))
+	}
+
+	fn leaves(&self) -> Option<Hazard> {
+		Some(Hazard::fire(self.position))
+	}
+}
+
+/// An area that keeps applying an effect to anything standing in it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Hazard {
+	pub position: Vec2,
+	pub radius: f32,
+	pub effect: StatusEffect,
+	pub lifetime: Cooldown,
+	/// Until the effect is next applied to whatever is standing in it
+	pub pulse: Cooldown,
+}
+
+impl Hazard {
+	const PULSE: Duration = Duration::from_millis(500);
+
+	pub fn fire(position: Vec2) -> Self {
+		Self {
+			position,
+			radius: 1.5,
+			effect: StatusEffect::burn(2.0, Duration::from_secs(1)),
+			lifetime: Cooldown::waiting(Duration::from_secs(4)),
+			pulse: Cooldown::ready(Hazard::PULSE),
+		}
+	}
+
+	/// What's left of a wall. Hard to get through quickly.
+	pub fn rubble(position: Vec2) -> Self {
+		Self {
+			position,
+			radius: 1.0,
+			effect: StatusEffect::slow(0.5, Duration::from_millis(600)),
+			lifetime: Cooldown::waiting(Duration::from_secs(6)),
+			pulse: Cooldown::ready(Hazard::PULSE),
+		}
+	}
+}
+
+impl Colideable for Hazard {
+	fn bounds(&self) -> BoundingCircle {
+		BoundingCircle {
+			position: self.position,
+			radius: self.radius,
+		}
+	}
+
+	fn position_mut(&mut self) -> &mut Vec2 {
+		&mut self.position
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{Achievements, Atlas, Audio, HighScores, Raster, Screen, Settings};
+
+	#[test]
+	fn marked_enemy_takes_crits() {
+		let mut enemy = Enemy {
+			position: Vec2::ZERO,
+			color: Color::YELLOW,
+			health: 25.0,
+			max_health: 25.0,
+			speed: 0.75,
+			cooldown: Cooldown::ready(Duration::from_secs(2)),
+			should_move_next_frame: true,
+			body: Body::new(1.0),
+			effects: Effects::default(),
+			last_hit: None,
+			animation: Animator::new("enemy_walk"),
+			bonus: false,
+		};
+
+		let mut bullet = Bullet::new(Vec2::ZERO, Vec2::new(1.0, 0.0), 4.0);
+		bullet.effect = Some(StatusEffect::vulnerable(0.5, Duration::from_secs(3)));
+
+		// The mark lands after the damage, so only the hits after the first
+		// do more than the bullet's damage and show as crits
+		let mut dealt = || {
+			let before = enemy.health;
+			enemy.hit(&bullet);
+			before - enemy.health
+		};
+		assert_eq!(dealt(), 4.0);
+		assert!(dealt() > 4.0);
+	}
+
+	#[test]
+	fn only_the_bonus_enemy_pays_out() {
+		let mut game = Game::new(
+			Box::new(Raster::new((64, 48))),
+			Settings::default(),
+			HighScores::default(),
+			Achievements::default(),
+			Audio::default(),
+			Screen::new((64, 48)),
+			Atlas::empty(),
+		);
+		let bonus = game.enemies.remove(0);
+		assert!(bonus.bonus);
+
+		// Looking like the bonus enemy isn't enough
+		let lookalike = Enemy {
+			bonus: false,
+			..bonus.clone()
+		};
+		game.enemy_killed(lookalike);
+		assert!(game.score < 1_000_000.0);
+
+		game.enemy_killed(bonus);
+		assert!(game.score >= 1_000_000.0);
 	}
 }
@@ -1,6 +1,9 @@
 use smitten::Vec2;
 
-use crate::{weapon::Bullet, BoundingCircle};
+use crate::{
+	physics::Physical, status::StatusEffect, thing::Hazard, util::segment_circle, weapon::Bullet,
+	BoundingCircle,
+};
 
 pub trait Colideable {
 	fn bounds(&self) -> BoundingCircle;
@@ -17,38 +20,99 @@ pub trait Colideable {
 pub trait Hittable: Colideable {
 	fn hit(&mut self, bullet: &Bullet);
 
-	fn was_hit(&self, bullet: &Bullet) -> bool {
+	/// How far along the path from `start` to `end` a bullet would hit us,
+	/// as a fraction of the path's length. Bullets are checked along their
+	/// whole path so fast ones can't skip over things.
+	fn hit_at(&self, start: Vec2, end: Vec2) -> Option<f32> {
 		let bounds = self.bounds();
-		bounds.position.distance_with(bullet.position) < (bounds.radius / 2.0)
+		segment_circle(start, end, bounds.position, bounds.radius / 2.0)
 	}
 }
 
+/// The index of the first thing a bullet going from `start` to `end` would
+/// hit and how far along the path it is.
+pub fn first_hit<H: Hittable>(hittables: &[H], start: Vec2, end: Vec2) -> Option<(usize, f32)> {
+	hittables
+		.iter()
+		.enumerate()
+		.filter_map(|(idx, h)| h.hit_at(start, end).map(|t| (idx, t)))
+		.min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
 pub trait Destructible {
 	fn health(&self) -> f32;
 	fn health_mut(&mut self) -> &mut f32;
+
+	/// What to multiply damage we take by
+	fn vulnerability(&self) -> f32 {
+		1.0
+	}
+
+	fn damage(&mut self, amount: f32) {
+		let amount = amount * self.vulnerability();
+		*self.health_mut() -= amount;
+	}
 }
 
 pub trait Explosive {
 	fn details(&self) -> ExplosiveDetails;
 
-	fn explode_on<T>(&self, thing: &mut T, knockback: bool)
+	/// How much of the blast reaches `thing` in the open, from 0 to 1
+	fn exposure<C: Colideable>(&self, thing: &C) -> f32 {
+		let details = self.details();
+		let distance = thing.bounds().position.distance_with(details.position);
+
+		falloff(distance / details.radius, details.falloff)
+	}
+
+	/// `cover` is how much of the blast gets through whatever is between us
+	/// and `thing`, from 0 to 1.
+	fn damage_on<C: Colideable>(&self, thing: &C, cover: f32) -> f32 {
+		self.details().damage * self.exposure(thing) * cover
+	}
+
+	fn explode_on<T>(&self, thing: &mut T, cover: f32)
 	where
 		T: Colideable + Destructible,
 	{
-		let direction = thing.bounds().position - self.details().position;
-		let magnitude = 1.0 / direction.length();
-		*thing.health_mut() -= self.details().damage * magnitude;
+		let damage = self.damage_on(thing, cover);
+		thing.damage(damage);
+	}
 
-		if knockback {
-			*thing.position_mut() += direction * magnitude;
-		}
+	/// Something left behind after we go off
+	fn leaves(&self) -> Option<Hazard> {
+		None
+	}
+
+	fn knock<T: Physical>(&self, thing: &mut T, cover: f32) {
+		let direction = (thing.bounds().position - self.details().position).normalize_correct();
+		let force = self.details().knockback * self.exposure(thing) * cover;
+
+		thing.body_mut().apply_impulse(direction * force);
 	}
 }
 
+/// How much of an explosion is left `t` of the way from its center to its
+/// edge, where the curve is `(1 - t) ^ power`
+pub fn falloff(t: f32, power: f32) -> f32 {
+	if t >= 1.0 {
+		return 0.0;
+	}
+
+	(1.0 - t.max(0.0)).powf(power)
+}
+
 pub struct ExplosiveDetails {
 	pub damage: f32,
 	pub position: Vec2,
 	pub radius: f32,
+	/// How sharply damage drops off toward the edge. 1 is linear, higher
+	/// keeps the damage near the center, and 0 is the same everywhere.
+	pub falloff: f32,
+	/// The impulse given to something at the center
+	pub knockback: f32,
+	/// Applied to everything caught in the blast
+	pub effect: Option<StatusEffect>,
 }
 
 impl ExplosiveDetails {
@@ -57,8 +121,26 @@ impl ExplosiveDetails {
 			damage,
 			position,
 			radius,
+			falloff: 1.0,
+			knockback: 0.0,
+			effect: None,
 		}
 	}
+
+	pub fn with_falloff(mut self, falloff: f32) -> Self {
+		self.falloff = falloff;
+		self
+	}
+
+	pub fn with_knockback(mut self, knockback: f32) -> Self {
+		self.knockback = knockback;
+		self
+	}
+
+	pub fn with_effect(mut self, effect: StatusEffect) -> Self {
+		self.effect = Some(effect);
+		self
+	}
 }
 
 impl Colideable for ExplosiveDetails {
@@ -73,3 +155,123 @@ impl Colideable for ExplosiveDetails {
 		&mut self.position
 	}
 }
+
+#[cfg(test)]
+mod test {
+	use std::time::Duration;
+
+	use smitten::Vec2;
+
+	use super::{falloff, first_hit, Colideable, Hittable};
+	use crate::{weapon::Bullet, BoundingCircle};
+
+	struct Target {
+		position: Vec2,
+		hit: bool,
+	}
+
+	impl Target {
+		fn new(x: f32, y: f32) -> Self {
+			Self {
+				position: Vec2::new(x, y),
+				hit: false,
+			}
+		}
+	}
+
+	impl Colideable for Target {
+		fn bounds(&self) -> BoundingCircle {
+			BoundingCircle {
+				position: self.position,
+				radius: 0.2,
+			}
+		}
+
+		fn position_mut(&mut self) -> &mut Vec2 {
+			&mut self.position
+		}
+	}
+
+	impl Hittable for Target {
+		fn hit(&mut self, _bullet: &Bullet) {
+			self.hit = true;
+		}
+	}
+
+	/// Where a bullet going `velocity` ends up after a tick of `delta`
+	fn fire(velocity: Vec2, delta: Duration) -> Vec2 {
+		velocity * delta.as_secs_f32()
+	}
+
+	#[test]
+	fn fast_bullet_hits_small_target() {
+		// A half second tick at bullet speed moves us 20 units, way past
+		// the target, but we went through it on the way.
+		let end = fire(Vec2::new(40.0, 0.0), Duration::from_millis(500));
+		let target = Target::new(10.0, 0.0);
+
+		assert!(target.bounds().position.distance_with(end) > 1.0);
+		let t = target.hit_at(Vec2::ZERO, end).unwrap();
+		// The near side of the half-radius circle, half way along
+		assert!((t - 0.495).abs() < 0.001);
+	}
+
+	#[test]
+	fn bullet_misses_target_off_path() {
+		let end = fire(Vec2::new(40.0, 0.0), Duration::from_millis(500));
+		let target = Target::new(10.0, 1.0);
+
+		assert_eq!(target.hit_at(Vec2::ZERO, end), None);
+	}
+
+	#[test]
+	fn bullet_stops_short_of_target() {
+		let target = Target::new(10.0, 0.0);
+
+		assert_eq!(target.hit_at(Vec2::ZERO, Vec2::new(9.0, 0.0)), None);
+		assert_eq!(
+			target.hit_at(Vec2::new(11.0, 0.0), Vec2::new(20.0, 0.0)),
+			None
+		);
+	}
+
+	#[test]
+	fn bullet_starting_inside_hits_straight_away() {
+		let target = Target::new(10.0, 0.0);
+
+		assert_eq!(
+			target.hit_at(Vec2::new(10.0, 0.0), Vec2::new(20.0, 0.0)),
+			Some(0.0)
+		);
+	}
+
+	#[test]
+	fn earliest_hit_wins() {
+		let bullet = Bullet::new(Vec2::ZERO, Vec2::new(40.0, 0.0), 1.0);
+		let end = fire(bullet.velocity, Duration::from_millis(500));
+		let targets = vec![
+			Target::new(15.0, 0.0),
+			Target::new(5.0, 0.0),
+			Target::new(10.0, 0.0),
+		];
+
+		let (idx, t) = first_hit(&targets, Vec2::ZERO, end).unwrap();
+		assert_eq!(idx, 1);
+		assert!((t - 0.245).abs() < 0.001);
+		assert_eq!(first_hit(&targets, Vec2::ZERO, Vec2::new(0.0, 20.0)), None);
+
+		let mut targets = targets;
+		targets[idx].hit(&bullet);
+		assert!(targets[1].hit);
+	}
+
+	#[test]
+	fn falloff_curves() {
+		assert_eq!(falloff(0.0, 1.0), 1.0);
+		assert_eq!(falloff(0.5, 1.0), 0.5);
+		assert_eq!(falloff(0.5, 2.0), 0.25);
+		assert_eq!(falloff(0.5, 0.0), 1.0);
+		assert_eq!(falloff(1.0, 0.0), 0.0);
+		assert_eq!(falloff(-1.0, 1.0), 1.0);
+	}
+}
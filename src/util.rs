@@ -1,5 +1,7 @@
 use std::time::Duration;
 
+use smitten::Vec2;
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Cooldown {
 	pub cooldown: Duration,
@@ -50,6 +52,10 @@ impl Cooldown {
 		}
 	}
 
+	pub fn remaining(&self) -> Duration {
+		self.waiting
+	}
+
 	pub fn percent(&self) -> f32 {
 		(self.cooldown.as_secs_f32() - self.waiting.as_secs_f32()) / self.cooldown.as_secs_f32()
 	}
@@ -60,3 +66,70 @@ enum State {
 	Waiting,
 	Ready,
 }
+
+pub fn dot(a: Vec2, b: Vec2) -> f32 {
+	a.x * b.x + a.y * b.y
+}
+
+pub fn cross(a: Vec2, b: Vec2) -> f32 {
+	a.x * b.y - a.y * b.x
+}
+
+/// Bounce `v` off a surface with the given normal
+pub fn reflect(v: Vec2, normal: Vec2) -> Vec2 {
+	v - normal * (2.0 * dot(v, normal))
+}
+
+/// How far along the segment from `start` to `end` it crosses the segment
+/// from `a` to `b`, as a fraction of the first segment's length.
+pub fn segment_segment(start: Vec2, end: Vec2, a: Vec2, b: Vec2) -> Option<f32> {
+	let along = end - start;
+	let edge = b - a;
+
+	let denominator = cross(along, edge);
+	if denominator == 0.0 {
+		// Parallel. Running along an edge isn't hitting it.
+		return None;
+	}
+
+	let offset = a - start;
+	let t = cross(offset, edge) / denominator;
+	let u = cross(offset, along) / denominator;
+
+	if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+		Some(t)
+	} else {
+		None
+	}
+}
+
+/// How far along the segment from `start` to `end` it first touches the
+/// circle, as a fraction of the segment's length. A segment that starts inside
+/// the circle touches it at 0.
+pub fn segment_circle(start: Vec2, end: Vec2, center: Vec2, radius: f32) -> Option<f32> {
+	let along = end - start;
+	let from_center = start - center;
+
+	let c = dot(from_center, from_center) - radius * radius;
+	if c <= 0.0 {
+		return Some(0.0);
+	}
+
+	let a = dot(along, along);
+	if a == 0.0 {
+		return None;
+	}
+
+	let b = 2.0 * dot(from_center, along);
+	let discriminant = b * b - 4.0 * a * c;
+	if discriminant < 0.0 {
+		return None;
+	}
+
+	let t = (-b - discriminant.sqrt()) / (2.0 * a);
+	if (0.0..=1.0).contains(&t) {
+		Some(t)
+	} else {
+		None
+	}
+}
@@ -1,9 +1,11 @@
 use std::time::{Duration, Instant};
 
-use rand::{thread_rng, Rng};
+use rand::{rngs::StdRng, Rng};
 use smitten::Vec2;
 
-use crate::util::Cooldown;
+use crate::{status::StatusEffect, util::Cooldown};
+
+const SPREADS: &str = include_str!("../spreads.txt");
 
 pub trait Weapon: core::fmt::Debug {
 	fn can_fire(&self) -> bool {
@@ -19,9 +21,29 @@ pub trait Weapon: core::fmt::Debug {
 	fn cooldown(&self) -> &Cooldown;
 	fn cooldown_mut(&mut self) -> &mut Cooldown;
 
-	fn bullets(&self, direction: Vec2) -> Vec<Bullet>;
+	/// How many times our bullets bounce off walls before they stop. Only
+	/// guns have any.
+	fn ricochet(&self) -> u32 {
+		0
+	}
+
+	fn ricochet_mut(&mut self) -> Option<&mut u32> {
+		None
+	}
+
+	/// Applied to whatever our bullets hit
+	fn effect(&self) -> Option<StatusEffect>;
+	fn effect_mut(&mut self) -> &mut Option<StatusEffect>;
+
+	fn bullets(&self, direction: Vec2, rng: &mut StdRng) -> Vec<Bullet>;
 
 	fn name(&self) -> &'static str;
+
+	/// Weapons that fire more than one pellet expose their spread so upgrades
+	/// can tweak it. Everything else has none.
+	fn spread_mut(&mut self) -> Option<&mut Spread> {
+		None
+	}
 }
 
 macro_rules! weapon_common_impl {
@@ -50,12 +72,33 @@ macro_rules! weapon_common_impl {
 			&mut self.cooldown
 		}
 
+		fn effect(&self) -> Option<StatusEffect> {
+			self.effect
+		}
+
+		fn effect_mut(&mut self) -> &mut Option<StatusEffect> {
+			&mut self.effect
+		}
+
 		fn name(&self) -> &'static str {
 			$name
 		}
 	};
 }
 
+/// For weapons that fire bullets
+macro_rules! gun_common_impl {
+	() => {
+		fn ricochet(&self) -> u32 {
+			self.ricochet
+		}
+
+		fn ricochet_mut(&mut self) -> Option<&mut u32> {
+			Some(&mut self.ricochet)
+		}
+	};
+}
+
 #[derive(Debug)]
 pub enum Ammunition {
 	Infinite,
@@ -105,6 +148,11 @@ pub struct Bullet {
 	pub velocity: Vec2,
 	pub birth: Instant,
 	pub damage: f32,
+	/// Bounces left before we stop at a wall
+	pub bounces: u32,
+	pub effect: Option<StatusEffect>,
+	/// The name of the weapon that fired us
+	pub weapon: &'static str,
 }
 
 impl Bullet {
@@ -114,6 +162,9 @@ impl Bullet {
 			velocity,
 			birth: Instant::now(),
 			damage,
+			bounces: 0,
+			effect: None,
+			weapon: "",
 		}
 	}
 }
@@ -123,13 +174,16 @@ pub struct Pistol {
 	cooldown: Cooldown,
 	ammo: Ammunition,
 	damage: f32,
+	ricochet: u32,
+	effect: Option<StatusEffect>,
 }
 
 impl Weapon for Pistol {
 	weapon_common_impl!("Pistol");
+	gun_common_impl!();
 
-	fn bullets(&self, direction: Vec2) -> Vec<Bullet> {
-		let direction = direction.angle() + thread_rng().gen_range(-5.0..5.0);
+	fn bullets(&self, direction: Vec2, rng: &mut StdRng) -> Vec<Bullet> {
+		let direction = direction.angle() + rng.gen_range(-5.0..5.0);
 
 		vec![Bullet::new(
 			Vec2::ZERO,
@@ -145,6 +199,8 @@ impl Default for Pistol {
 			cooldown: Cooldown::ready(Duration::from_secs_f32(0.5)),
 			ammo: Ammunition::Infinite,
 			damage: 7.5,
+			ricochet: 0,
+			effect: None,
 		}
 	}
 }
@@ -154,13 +210,16 @@ pub struct Uzi {
 	cooldown: Cooldown,
 	ammo: Ammunition,
 	damage: f32,
+	ricochet: u32,
+	effect: Option<StatusEffect>,
 }
 
 impl Weapon for Uzi {
 	weapon_common_impl!("Uzi");
+	gun_common_impl!();
 
-	fn bullets(&self, direction: Vec2) -> Vec<Bullet> {
-		let direction = direction.angle() + thread_rng().gen_range(-5.0..5.0);
+	fn bullets(&self, direction: Vec2, rng: &mut StdRng) -> Vec<Bullet> {
+		let direction = direction.angle() + rng.gen_range(-5.0..5.0);
 
 		vec![Bullet::new(
 			Vec2::ZERO,
@@ -179,34 +238,288 @@ impl Default for Uzi {
 				rounds: 0,
 			},
 			damage: 6.5,
+			ricochet: 0,
+			effect: None,
 		}
 	}
 }
 
+/// How the pellets of a multi-shot weapon are laid out. Angles and widths are
+/// in degrees.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpreadPattern {
+	/// Pellets evenly spaced across `width`, centered on where we're aiming
+	Fan { pellets: u32, width: f32 },
+	/// Every pellet gets a random angle somewhere within `width`
+	Cone { pellets: u32, width: f32 },
+	/// Evenly spaced all the way around, starting where we're aiming
+	Ring { pellets: u32 },
+	/// One pellet per angle, each relative to where we're aiming
+	Custom(Vec<f32>),
+	/// Every pellet gets a random angle within `± inaccuracy`, and each one
+	/// after the first strays `step` further than the last
+	Widening {
+		pellets: u32,
+		inaccuracy: f32,
+		step: f32,
+	},
+}
+
+impl SpreadPattern {
+	fn parse(kind: &str, numbers: &[f32]) -> Option<Self> {
+		// A pattern that fires nothing, or part of a pellet, is a mistake
+		let count =
+			|pellets: f32| (pellets >= 1.0 && pellets.fract() == 0.0).then_some(pellets as u32);
+
+		let pattern = match (kind, numbers) {
+			("fan", [pellets, width]) => SpreadPattern::Fan {
+				pellets: count(*pellets)?,
+				width: *width,
+			},
+			("cone", [pellets, width]) => SpreadPattern::Cone {
+				pellets: count(*pellets)?,
+				width: *width,
+			},
+			("ring", [pellets]) => SpreadPattern::Ring {
+				pellets: count(*pellets)?,
+			},
+			("custom", angles) if !angles.is_empty() => SpreadPattern::Custom(angles.to_vec()),
+			("widening", [pellets, inaccuracy, step]) => SpreadPattern::Widening {
+				pellets: count(*pellets)?,
+				inaccuracy: *inaccuracy,
+				step: *step,
+			},
+			_ => return None,
+		};
+
+		Some(pattern)
+	}
+}
+
+/// What the wide shot upgrade does to a spread
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Widen {
+	pub pellets: i32,
+	pub scale: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Spread {
+	pub pattern: SpreadPattern,
+	/// Each pellet's damage is scaled by a random value in `1.0 ± damage_variance`
+	pub damage_variance: f32,
+	/// Each pellet's speed is scaled by a random value in `1.0 ± speed_variance`
+	pub speed_variance: f32,
+	pub widen: Widen,
+}
+
+impl Spread {
+	pub fn new(pattern: SpreadPattern) -> Self {
+		Self {
+			pattern,
+			damage_variance: 0.0,
+			speed_variance: 0.0,
+			widen: Widen {
+				pellets: 0,
+				scale: 1.0,
+			},
+		}
+	}
+
+	/// The spread for `weapon` in `spreads.txt`. Weapons that aren't in there
+	/// fire one pellet straight ahead.
+	pub fn load(weapon: &str) -> Self {
+		let mut spread = Self::new(SpreadPattern::Fan {
+			pellets: 1,
+			width: 0.0,
+		});
+
+		let lines = SPREADS
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+		for line in lines {
+			let words: Vec<&str> = line.split_whitespace().collect();
+			if words.first() != Some(&weapon) {
+				continue;
+			}
+
+			if spread.set(&words[1..]).is_none() {
+				eprintln!("Couldn't understand spread: {line}");
+			}
+		}
+
+		spread
+	}
+
+	/// Set one line of `spreads.txt`, without the weapon name
+	fn set(&mut self, words: &[&str]) -> Option<()> {
+		let numbers = words
+			.iter()
+			.skip(match *words.first()? {
+				"pattern" => 2,
+				_ => 1,
+			})
+			.map(|word| word.parse().ok())
+			.collect::<Option<Vec<f32>>>()?;
+
+		match (*words.first()?, &numbers[..]) {
+			("pattern", _) => self.pattern = SpreadPattern::parse(words.get(1)?, &numbers)?,
+			("variance", [damage, speed]) => {
+				self.damage_variance = *damage;
+				self.speed_variance = *speed;
+			}
+			("widen", [pellets, scale]) => {
+				self.widen = Widen {
+					pellets: *pellets as i32,
+					scale: *scale,
+				}
+			}
+			_ => return None,
+		}
+
+		Some(())
+	}
+
+	/// Apply the wide shot upgrade
+	pub fn widen(&mut self) {
+		self.add_pellets(self.widen.pellets);
+		self.scale_width(self.widen.scale);
+	}
+
+	/// The absolute angle of every pellet when aiming at `direction` degrees.
+	pub fn angles(&self, direction: f32, rng: &mut StdRng) -> Vec<f32> {
+		match &self.pattern {
+			SpreadPattern::Fan { pellets, width } => {
+				if *pellets <= 1 {
+					return vec![direction; *pellets as usize];
+				}
+
+				let step = width / (*pellets - 1) as f32;
+				(0..*pellets)
+					.map(|idx| direction - width / 2.0 + step * idx as f32)
+					.collect()
+			}
+			SpreadPattern::Cone { pellets, width } => {
+				let half = width / 2.0;
+				(0..*pellets)
+					.map(|_| {
+						if half > 0.0 {
+							direction + rng.gen_range(-half..half)
+						} else {
+							direction
+						}
+					})
+					.collect()
+			}
+			SpreadPattern::Ring { pellets } => {
+				let step = 360.0 / (*pellets).max(1) as f32;
+				(0..*pellets)
+					.map(|idx| direction + step * idx as f32)
+					.collect()
+			}
+			SpreadPattern::Custom(offsets) => {
+				offsets.iter().map(|offset| direction + offset).collect()
+			}
+			SpreadPattern::Widening {
+				pellets,
+				inaccuracy,
+				step,
+			} => (0..*pellets)
+				.map(|idx| {
+					let half = inaccuracy + step * idx as f32;
+					if half > 0.0 {
+						direction + rng.gen_range(-half..half)
+					} else {
+						direction
+					}
+				})
+				.collect(),
+		}
+	}
+
+	/// Custom patterns have an exact list of angles so their pellet count
+	/// can't be changed this way.
+	pub fn add_pellets(&mut self, count: i32) {
+		match &mut self.pattern {
+			SpreadPattern::Fan { pellets, .. }
+			| SpreadPattern::Cone { pellets, .. }
+			| SpreadPattern::Ring { pellets }
+			| SpreadPattern::Widening { pellets, .. } => {
+				*pellets = (*pellets as i32 + count).max(1) as u32;
+			}
+			SpreadPattern::Custom(_) => (),
+		}
+	}
+
+	/// Rings always cover the full circle and aren't affected.
+	pub fn scale_width(&mut self, scalar: f32) {
+		match &mut self.pattern {
+			SpreadPattern::Fan { width, .. } | SpreadPattern::Cone { width, .. } => {
+				*width *= scalar
+			}
+			SpreadPattern::Custom(offsets) => offsets.iter_mut().for_each(|o| *o *= scalar),
+			SpreadPattern::Widening {
+				inaccuracy, step, ..
+			} => {
+				*inaccuracy *= scalar;
+				*step *= scalar;
+			}
+			SpreadPattern::Ring { .. } => (),
+		}
+	}
+
+	pub fn bullets(
+		&self,
+		direction: Vec2,
+		speed: f32,
+		damage: f32,
+		rng: &mut StdRng,
+	) -> Vec<Bullet> {
+		let angles = self.angles(direction.angle(), rng);
+		let mut vary = |variance: f32| {
+			if variance > 0.0 {
+				1.0 + rng.gen_range(-variance..variance)
+			} else {
+				1.0
+			}
+		};
+
+		angles
+			.into_iter()
+			.map(|angle| {
+				Bullet::new(
+					Vec2::ZERO,
+					Vec2::from_degrees(angle) * speed * vary(self.speed_variance),
+					damage * vary(self.damage_variance),
+				)
+			})
+			.collect()
+	}
+}
+
 #[derive(Debug)]
 pub struct Shotgun {
 	cooldown: Cooldown,
 	ammo: Ammunition,
 	damage: f32,
+	ricochet: u32,
+	effect: Option<StatusEffect>,
+	spread: Spread,
 }
 
 impl Weapon for Shotgun {
 	weapon_common_impl!("Shotgun");
+	gun_common_impl!();
 
-	fn bullets(&self, direction: Vec2) -> Vec<Bullet> {
-		let mut inaccuracy = 1.5;
-		std::iter::repeat_with(|| {
-			let direction = direction.angle() + thread_rng().gen_range(-inaccuracy..inaccuracy);
-			inaccuracy += 4.25;
-
-			Bullet::new(
-				Vec2::ZERO,
-				Vec2::from_degrees(direction) * crate::Game::BULLET_SPEED,
-				self.damage,
-			)
-		})
-		.take(3)
-		.collect()
+	fn bullets(&self, direction: Vec2, rng: &mut StdRng) -> Vec<Bullet> {
+		self.spread
+			.bullets(direction, crate::Game::BULLET_SPEED, self.damage, rng)
+	}
+
+	fn spread_mut(&mut self) -> Option<&mut Spread> {
+		Some(&mut self.spread)
 	}
 }
 
@@ -219,6 +532,9 @@ impl Default for Shotgun {
 				rounds: 0,
 			},
 			damage: 15.0,
+			ricochet: 0,
+			effect: None,
+			spread: Spread::load("shotgun"),
 		}
 	}
 }
@@ -228,12 +544,13 @@ pub struct Wall {
 	cooldown: Cooldown,
 	ammo: Ammunition,
 	damage: f32,
+	effect: Option<StatusEffect>,
 }
 
 impl Weapon for Wall {
 	weapon_common_impl!("Walls");
 
-	fn bullets(&self, _direction: Vec2) -> Vec<Bullet> {
+	fn bullets(&self, _direction: Vec2, _rng: &mut StdRng) -> Vec<Bullet> {
 		unreachable!("Called bullets on wall")
 	}
 }
@@ -247,6 +564,7 @@ impl Default for Wall {
 				rounds: 0,
 			},
 			damage: 0.0,
+			effect: None,
 		}
 	}
 }
@@ -256,12 +574,13 @@ pub struct Barrel {
 	cooldown: Cooldown,
 	ammo: Ammunition,
 	damage: f32,
+	effect: Option<StatusEffect>,
 }
 
 impl Weapon for Barrel {
 	weapon_common_impl!("Barrels");
 
-	fn bullets(&self, _direction: Vec2) -> Vec<Bullet> {
+	fn bullets(&self, _direction: Vec2, _rng: &mut StdRng) -> Vec<Bullet> {
 		unreachable!("Called bullets on barrel")
 	}
 }
@@ -275,6 +594,105 @@ impl Default for Barrel {
 				rounds: 0,
 			},
 			damage: 0.0,
+			effect: None,
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use rand::SeedableRng;
+
+	use super::*;
+
+	fn set(line: &str) -> Option<Spread> {
+		let mut spread = Spread::load("nothing");
+		let words: Vec<&str> = line.split_whitespace().collect();
+		spread.set(&words).map(|_| spread)
+	}
+
+	#[test]
+	fn shipped_spreads_parse() {
+		let lines = SPREADS
+			.lines()
+			.map(str::trim)
+			.filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+		for line in lines {
+			let words: Vec<&str> = line.split_whitespace().collect();
+			assert!(set(&words[1..].join(" ")).is_some(), "{line}");
 		}
 	}
+
+	#[test]
+	fn shotgun() {
+		let spread = Spread::load("shotgun");
+		assert_eq!(
+			spread.pattern,
+			SpreadPattern::Widening {
+				pellets: 3,
+				inaccuracy: 1.5,
+				step: 4.25,
+			}
+		);
+		assert_eq!(
+			spread.widen,
+			Widen {
+				pellets: 2,
+				scale: 1.5,
+			}
+		);
+	}
+
+	#[test]
+	fn bad_lines_are_refused() {
+		for line in [
+			"pattern fan 0 30",
+			"pattern ring 0",
+			"pattern widening 0 1 1",
+			"pattern cone 2.5 30",
+			"pattern fan -1 30",
+			"pattern fan 3",
+			"pattern custom",
+			"pattern spiral 3",
+			"variance 0.1",
+			"widen two 1.5",
+			"recoil 5",
+		] {
+			assert_eq!(set(line), None, "{line}");
+		}
+	}
+
+	#[test]
+	fn widened_shotgun_angles() {
+		let mut spread = Spread::load("shotgun");
+		spread.widen();
+		assert_eq!(
+			spread.pattern,
+			SpreadPattern::Widening {
+				pellets: 5,
+				inaccuracy: 2.25,
+				step: 6.375,
+			}
+		);
+
+		// Each pellet can stray further than the last
+		let mut rng = StdRng::seed_from_u64(0);
+		for _ in 0..100 {
+			let angles = spread.angles(90.0, &mut rng);
+			assert_eq!(angles.len(), 5);
+
+			for (idx, angle) in angles.into_iter().enumerate() {
+				let half = 2.25 + 6.375 * idx as f32;
+				assert!((angle - 90.0).abs() <= half, "pellet {idx} at {angle}");
+			}
+		}
+	}
+
+	#[test]
+	fn fan_is_centered() {
+		let spread = set("pattern fan 3 30").unwrap();
+		let mut rng = StdRng::seed_from_u64(0);
+		assert_eq!(spread.angles(0.0, &mut rng), vec![-15.0, 0.0, 15.0]);
+	}
 }